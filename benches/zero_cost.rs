@@ -0,0 +1,90 @@
+//! Compares scoped operations against the same changes reverted by hand, to check whether the
+//! abstraction compiles away. Run with `cargo bench`; if it's zero-cost, each pair of timings
+//! should be about the same.
+//!
+//! This uses a tiny hand-rolled harness instead of a benchmarking crate, so that the crate stays
+//! free of dependencies and this runs on stable.
+
+use scoped_ops::borrowed::VecScoped;
+use std::hint::black_box;
+use std::time::Instant;
+
+const ITERS: u32 = 10_000_000;
+
+/// Run `f` on `a` many times and return the average number of nanoseconds per iteration
+fn time(a: &mut Vec<i32>, f: fn(&mut Vec<i32>)) -> f64 {
+    // Warm up so that the first measurement isn't penalized
+    for _ in 0..ITERS / 10 {
+        f(a);
+    }
+    let start = Instant::now();
+    for _ in 0..ITERS {
+        f(a);
+    }
+    start.elapsed().as_secs_f64() * 1e9 / f64::from(ITERS)
+}
+
+/// Time both versions, checking that each one leaves `a` untouched
+fn compare(name: &str, manual: fn(&mut Vec<i32>), scoped: fn(&mut Vec<i32>)) {
+    let mut a = vec![1, 2, 3];
+    let manual = time(&mut a, manual);
+    assert_eq!([1, 2, 3], *a);
+    let scoped = time(&mut a, scoped);
+    assert_eq!([1, 2, 3], *a);
+    println!(
+        "{:<12} manual: {:>7.2} ns  scoped: {:>7.2} ns",
+        name, manual, scoped
+    );
+}
+
+fn main() {
+    compare(
+        "push",
+        |a| {
+            a.push(black_box(4));
+            black_box(&a);
+            a.pop();
+        },
+        |a| {
+            let b = a.pushed(black_box(4));
+            black_box(&*b);
+        },
+    );
+
+    compare(
+        "assign",
+        |a| {
+            let previous = core::mem::replace(&mut a[1], black_box(4));
+            black_box(&a);
+            a[1] = previous;
+        },
+        |a| {
+            let b = a.assigned(1, black_box(4));
+            black_box(&*b);
+        },
+    );
+
+    compare(
+        "nested (8)",
+        |a| {
+            for i in 0..8 {
+                a.push(black_box(i));
+            }
+            black_box(&a);
+            for _ in 0..8 {
+                a.pop();
+            }
+        },
+        |a| {
+            let mut b1 = a.pushed(black_box(0));
+            let mut b2 = b1.pushed(black_box(1));
+            let mut b3 = b2.pushed(black_box(2));
+            let mut b4 = b3.pushed(black_box(3));
+            let mut b5 = b4.pushed(black_box(4));
+            let mut b6 = b5.pushed(black_box(5));
+            let mut b7 = b6.pushed(black_box(6));
+            let b8 = b7.pushed(black_box(7));
+            black_box(&*b8);
+        },
+    );
+}
@@ -29,12 +29,12 @@
 //!
 //! The approach here gives each mutating operation a scope, reverting the operation when it goes
 //! out of scope. So once the operation goes out of scope, the state of the data structure will be
-//! returned to what it was before the scoped operation was applied (except for maybe like the
-//! capacity of a `Vec` could be different, or something like that). Because each operation is
-//! reverted when it goes out of scope, operations can be nested without complication. Conceptually
-//! this is like a weaker version of a partially persistent data structure. Unlike in a partially
-//! persistent data structure, you can't actually "see" any past state; you have to apply undo
-//! operations until you get back to that state.
+//! returned to what it was before the scoped operation was applied (except that the capacity of a
+//! `Vec` could be larger: applying or reverting an operation never shrinks the buffer). Because
+//! each operation is reverted when it goes out of scope, operations can be nested without
+//! complication. Conceptually this is like a weaker version of a partially persistent data
+//! structure. Unlike in a partially persistent data structure, you can't actually "see" any past
+//! state; you have to apply undo operations until you get back to that state.
 //!
 //! ## The Promise
 //!
@@ -42,7 +42,9 @@
 //!
 //! - Uses normal Rust data structures, so can be integrated into existing code
 //! - Hopefully zero-cost abstraction, although inspection would be needed to verify this
-//! - No dependencies, could work without `std`
+//! - No dependencies, could work without `std` (it does: disable the default `std` feature). The
+//!   optional `log` feature adds a dependency on `log`, to trace each operation as it's applied and
+//!   reverted, and the optional `serde` feature adds one on `serde`, to serialize the current state
 //!
 //! ## The Reality
 //!
@@ -88,48 +90,648 @@
 //!   - When to use associated types vs. generic types
 //!   - How to emulate a [sealed trait](https://rust-lang.github.io/api-guidelines/future-proofing.html)
 //!   - `&mut T` is not [`UnwindSafe`](https://doc.rust-lang.org/std/panic/trait.UnwindSafe.html),
-//!     meaning I can't test panics with this crate (without learning more about unwind safety).
+//!     so testing panics with this crate means wrapping the closure in `AssertUnwindSafe`. That's
+//!     fine here, because unwinding drops the scoped values that have already been built, which
+//!     reverts them. The closures and iterators given to operations like `retained`, `mapped`,
+//!     `extended`, and `spliced` are also safe to panic in: the `Vec` is left as it was. But a
+//!     panic in `with_undo`'s `apply`, or in a `Clone`, `Ord`, or `PartialEq` implementation while
+//!     an operation like `sorted` or `deduped` is being applied, isn't reverted and can leave the
+//!     `Vec` partly changed.
 //!
 //! # The Future
 //!
 //! A few possible interesting angles on future exploration:
 //!
-//! - Check whether this is actually, as I hoped, a zero-cost abstraction
+//! - Check whether this is actually, as I hoped, a zero-cost abstraction. `cargo bench` times some
+//!   scoped operations against the same changes reverted by hand, as a starting point
 //! - Figure out if this would actually be useful for anything 😂
-//! - Explore support for "commit vs. revert"
+//! - Explore more ways to choose between committing and reverting. Every operation can already
+//!   `commit`, `run_scoped_committed` decides at runtime, and owned operations can be `leak`ed
 //! - Add more operations to `Vec`
 //! - Add support for other data structures
-//! - Explore a reference-counted variant
 //!
 //! Thanks to mjhoy for contributing the `Assign` operation, and thanks to everyone on
 //! [the URLO thread](https://users.rust-lang.org/t/pattern-for-nested-mutable-references/45651) who
 //! suggested great solutions for nesting mutable types!
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+/// Apply a chain of scoped operations from left to right, then run a body with the final scoped
+/// value bound to the original name. This avoids writing out a `let` binding for each operation.
+///
+/// ```
+/// use scoped_ops::scoped;
+/// use scoped_ops::borrowed::VecScoped;
+///
+/// let mut a = vec![1, 2, 3];
+/// scoped! { a => pushed(4), assigned(1, -2);
+///     assert_eq!([1, -2, 3, 4], *a);
+/// }
+/// assert_eq!([1, 2, 3], *a);
+/// ```
+#[macro_export]
+macro_rules! scoped {
+    ($name:ident => ; $($body:tt)*) => {{
+        $($body)*
+    }};
+    ($name:ident => $op:ident($($arg:expr),* $(,)?) $(, $rest_op:ident($($rest_arg:expr),* $(,)?))*;
+        $($body:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut $name = $name.$op($($arg),*);
+        $crate::scoped! { $name => $($rest_op($($rest_arg),*)),*; $($body)* }
+    }};
+}
+
+/// Implements traits that delegate to the current slice for wrappers that deref to one: `PartialEq`
+/// against slices, arrays and `Vec`s (so `a.pushed(4) == [1, 2, 3, 4]` works), iteration by
+/// reference, indexing (so `a.pushed(4)[3]` works, and is assignable where `DerefMut` is),
+/// `AsRef<[T]>` and `Borrow<[T]>`, `Display` as a bracketed list like `[1, 2, 3]`, `Hash` the same
+/// way as the slice, and, with the `serde` feature, `Serialize` as a sequence. This also adds an
+/// inherent `as_slice`, for when going through `Deref` is ambiguous.
+macro_rules! impl_slice_traits {
+    ($bound:ident: $($ty:ident $(<$lt:lifetime>)?),* $(,)?) => {$(
+        impl<$($lt,)? T, W> $ty<$($lt,)? W>
+        where
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            /// The current contents, without going through `Deref`
+            pub fn as_slice(&self) -> &[T] {
+                self
+            }
+        }
+
+        impl<$($lt,)? T, W> AsRef<[T]> for $ty<$($lt,)? W>
+        where
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn as_ref(&self) -> &[T] {
+                self
+            }
+        }
+
+        impl<$($lt,)? T, W> core::borrow::Borrow<[T]> for $ty<$($lt,)? W>
+        where
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn borrow(&self) -> &[T] {
+                self
+            }
+        }
+
+        impl<$($lt,)? T, U, W> PartialEq<[U]> for $ty<$($lt,)? W>
+        where
+            T: PartialEq<U>,
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn eq(&self, other: &[U]) -> bool {
+                **self == *other
+            }
+        }
+
+        impl<'b, $($lt,)? T, U, W> PartialEq<&'b [U]> for $ty<$($lt,)? W>
+        where
+            T: PartialEq<U>,
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn eq(&self, other: &&'b [U]) -> bool {
+                **self == **other
+            }
+        }
+
+        impl<$($lt,)? T, U, W, const N: usize> PartialEq<[U; N]> for $ty<$($lt,)? W>
+        where
+            T: PartialEq<U>,
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn eq(&self, other: &[U; N]) -> bool {
+                **self == other[..]
+            }
+        }
+
+        impl<$($lt,)? T, U, W> PartialEq<alloc::vec::Vec<U>> for $ty<$($lt,)? W>
+        where
+            T: PartialEq<U>,
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn eq(&self, other: &alloc::vec::Vec<U>) -> bool {
+                **self == other[..]
+            }
+        }
+
+        impl<'b, $($lt,)? T: 'b, W> IntoIterator for &'b $ty<$($lt,)? W>
+        where
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            type Item = &'b T;
+            type IntoIter = core::slice::Iter<'b, T>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter()
+            }
+        }
+
+        impl<$($lt,)? T, W> core::fmt::Display for $ty<$($lt,)? W>
+        where
+            T: core::fmt::Display,
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str("[")?;
+                for (i, element) in self.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    core::fmt::Display::fmt(element, f)?;
+                }
+                f.write_str("]")
+            }
+        }
+
+        impl<$($lt,)? T, W> core::hash::Hash for $ty<$($lt,)? W>
+        where
+            T: core::hash::Hash,
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+                (**self).hash(state)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<$($lt,)? T, W> serde::Serialize for $ty<$($lt,)? W>
+        where
+            T: serde::Serialize,
+            W: core::ops::Deref<Target = [T]> + $bound,
+        {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                (**self).serialize(serializer)
+            }
+        }
+
+        impl<$($lt,)? I, W: $bound> core::ops::Index<I> for $ty<$($lt,)? W>
+        where
+            Self: core::ops::Deref,
+            <Self as core::ops::Deref>::Target: core::ops::Index<I>,
+        {
+            type Output = <<Self as core::ops::Deref>::Target as core::ops::Index<I>>::Output;
+
+            fn index(&self, index: I) -> &Self::Output {
+                &(**self)[index]
+            }
+        }
+
+        /// Like `DerefMut`, this can't change the length, and the edits persist past this scope.
+        /// Operations still revert by position, e.g. `Insert` removes whatever is at its index by
+        /// then, so moving elements around can make them undo the wrong ones.
+        impl<$($lt,)? I, W: $bound> core::ops::IndexMut<I> for $ty<$($lt,)? W>
+        where
+            Self: core::ops::DerefMut,
+            <Self as core::ops::Deref>::Target: core::ops::IndexMut<I>,
+        {
+            fn index_mut(&mut self, index: I) -> &mut Self::Output {
+                &mut (**self)[index]
+            }
+        }
+    )*};
+}
+
 pub mod borrowed {
-    /// Everything that is `VecScoped` will need to have mutable access to the underlying `Vec`.
-    /// However, only the trait implementations should be allowed to mutate the `Vec`; end users
-    /// should not, because they could violate an invariant.
+    /// Everything that is `VecScoped` will need to have access to the underlying `Vec`. However,
+    /// only the trait implementations should be allowed to mutate the `Vec`; end users should not,
+    /// because they could violate an invariant.
+    ///
+    /// Reverting relies on operations being reverted in the opposite order that they were applied.
+    /// Each operation holds the only `&mut` to the one before it, so the borrow checker won't let an
+    /// outer operation be dropped, moved, or swapped out while an inner one is still alive:
+    ///
+    /// ```compile_fail
+    /// use scoped_ops::borrowed::VecScoped;
+    ///
+    /// let mut a = vec![1];
+    /// let mut b = a.pushed(2);
+    /// let c = b.pushed(3);
+    /// drop(b);
+    /// drop(c);
+    /// ```
+    ///
+    /// An inner operation can still skip its revert, though, by being committed or passed to
+    /// `mem::forget`, and then the outer one undoes the wrong change. In debug builds with `std`,
+    /// the operations applied to each `Vec` are counted, so these cases panic instead.
     pub trait VecScopedPrivate {
         type Element;
 
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element>;
+        /// The storage at the bottom of the chain, e.g. `Vec<Self::Element>`
+        type Storage: PushPopStorage<Element = Self::Element>;
+
+        fn storage_ref(&self) -> &Self::Storage;
+
+        fn storage_mut(&mut self) -> &mut Self::Storage;
+
+        /// The number of scoped operations between this and the underlying `Vec`
+        fn vec_depth(&self) -> usize;
+
+        /// Add the index and previous element of each assignment in the chain that is waiting to be
+        /// restored, in the order that they'll be restored in
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>);
+    }
+
+    /// The operations that `pushed`, `popped`, `assigned`, and `nooped` need from the underlying
+    /// storage. This is implemented for `Vec`; implement it for your own buffer type (e.g. a
+    /// fixed-capacity stack) to use those operations on it. Everything else needs a `Vec`.
+    pub trait PushPopStorage {
+        type Element;
+
+        fn push(&mut self, value: Self::Element);
+
+        fn pop(&mut self) -> Option<Self::Element>;
+
+        fn get_mut(&mut self, idx: usize) -> Option<&mut Self::Element>;
+
+        fn len(&self) -> usize;
+
+        fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+    }
+
+    /// A `VecScopedPrivate` whose storage is a `Vec`, which most operations need
+    pub trait VecBacked:
+        VecScopedPrivate<Storage = Vec<<Self as VecScopedPrivate>::Element>>
+    {
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.storage_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.storage_mut()
+        }
+    }
+
+    impl<V: VecScopedPrivate<Storage = Vec<<V as VecScopedPrivate>::Element>>> VecBacked for V {}
+
+    use alloc::boxed::Box;
+    use alloc::collections::{BTreeMap, BinaryHeap, VecDeque};
+    #[cfg(test)]
+    use alloc::format;
+    use alloc::string::String;
+    #[cfg(test)]
+    use alloc::string::ToString;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::fmt;
+    use core::mem::ManuallyDrop;
+    use core::ops::{Bound, Deref, DerefMut, Range, RangeBounds};
+
+    /// Counts how many operations are applied to each `Vec`, to check in debug builds with `std`
+    /// that they're reverted in strict LIFO order. Each operation is at a fixed depth in its chain,
+    /// so it must be the last of the live ones when it's applied, reverted, or committed. Without
+    /// `debug_assertions` or `std`, these functions do nothing.
+    mod generation {
+        use super::VecScopedPrivate;
+        #[cfg(all(debug_assertions, feature = "std"))]
+        use alloc::collections::BTreeMap;
+        #[cfg(all(debug_assertions, feature = "std"))]
+        use std::sync::Mutex;
+
+        /// The number of live operations, keyed by the address of the storage under them. Storage
+        /// with nothing applied has no entry.
+        #[cfg(all(debug_assertions, feature = "std"))]
+        static LIVE: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+        /// Sets the number of live operations on the storage under `op`, returning the old number
+        #[cfg(all(debug_assertions, feature = "std"))]
+        fn set_live<V: VecScopedPrivate>(op: &V, count: usize) -> usize {
+            let key = op.storage_ref() as *const V::Storage as usize;
+            let mut live = LIVE.lock().unwrap_or_else(|err| err.into_inner());
+            let old = if count == 0 {
+                live.remove(&key)
+            } else {
+                live.insert(key, count)
+            };
+            old.unwrap_or(0)
+        }
+
+        /// Record that an operation was applied on top of `inner`
+        pub(super) fn applied<V: VecScopedPrivate>(_inner: &V) {
+            #[cfg(all(debug_assertions, feature = "std"))]
+            {
+                let depth = _inner.vec_depth() + 1;
+                let old = set_live(_inner, depth);
+                if old + 1 != depth {
+                    // The new operation won't be built, so leave it out of the count
+                    set_live(_inner, depth - 1);
+                    debug_assert!(
+                        false,
+                        "applied an operation at depth {} on top of {} live operations; one of them \
+                         was committed or forgotten beneath another",
+                        depth, old
+                    );
+                }
+            }
+        }
+
+        /// Record that `op` is being reverted
+        pub(super) fn reverted<V: VecScopedPrivate>(_op: &V) {
+            #[cfg(all(debug_assertions, feature = "std"))]
+            {
+                let depth = _op.vec_depth();
+                let old = set_live(_op, depth - 1);
+                // Panicking while already unwinding would abort, so only the count is fixed then
+                if old != depth && !std::thread::panicking() {
+                    debug_assert!(
+                        false,
+                        "reverted an operation at depth {} with {} live operations, so it would \
+                         undo the wrong change; one above it was committed or forgotten",
+                        depth, old
+                    );
+                }
+            }
+        }
+
+        /// Record that `op` is being committed, which is only allowed right on top of the storage
+        pub(super) fn committed<V: VecScopedPrivate>(_op: &V) {
+            #[cfg(all(debug_assertions, feature = "std"))]
+            {
+                let depth = _op.vec_depth();
+                debug_assert!(
+                    depth == 1,
+                    "committed an operation at depth {}, which would make the operations beneath \
+                     it revert the wrong changes",
+                    depth
+                );
+                set_live(_op, 0);
+            }
+        }
     }
 
-    use std::ops::Deref;
+    /// Emits a `log` trace message with the `log` feature, and does nothing otherwise
+    macro_rules! trace {
+        ($($arg:tt)*) => {
+            #[cfg(feature = "log")]
+            log::trace!($($arg)*);
+        };
+    }
+
+    /// Implements `into_inner` for wrappers, given the field that holds the mutable reference
+    macro_rules! impl_into_inner {
+        ($bound:ident: $($ty:ident.$field:tt),* $(,)?) => {$(
+            impl<'a, W: $bound> $ty<'a, W> {
+                /// Revert this operation right away and give back the mutable reference that it was
+                /// applied to, instead of waiting for it to be dropped.
+                pub fn into_inner(self) -> &'a mut W {
+                    let mut this = ManuallyDrop::new(self);
+                    // SAFETY: `this` is never used again except to read the reference, and
+                    // `ManuallyDrop::drop` leaves the memory unchanged apart from what `Drop`
+                    // itself does. Dropping doesn't change the reference, which has no drop glue
+                    // and is still valid for `'a`, so the revert runs exactly once.
+                    unsafe {
+                        ManuallyDrop::drop(&mut this);
+                        core::ptr::read(&this.$field)
+                    }
+                }
+            }
+        )*};
+    }
+
+    /// Implements `Debug` for wrappers by formatting whatever they deref to, e.g. `Push([1, 2])`
+    macro_rules! impl_debug_via_deref {
+        ($bound:ident: $($ty:ident),* $(,)?) => {$(
+            impl<'a, W: $bound> fmt::Debug for $ty<'a, W>
+            where
+                Self: Deref,
+                <Self as Deref>::Target: fmt::Debug,
+            {
+                fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                    f.debug_tuple(stringify!($ty)).field(&&**self).finish()
+                }
+            }
+        )*};
+    }
+
+    /// Implements `op_kind` for `Vec` wrappers, each of which has an `OpKind` of the same name
+    macro_rules! impl_op_kind {
+        ($bound:ident: $($ty:ident $(<$param:ident: $param_bound:path>)?),* $(,)?) => {$(
+            impl<'a, V: $bound $(, $param: $param_bound)?> $ty<'a, V $(, $param)?> {
+                /// Which kind of operation this is, for inspecting a chain at runtime
+                pub fn op_kind(&self) -> OpKind {
+                    OpKind::$ty
+                }
+            }
+        )*};
+    }
 
     /// This trait represent a `Vec` or a temporary modification of a `Vec`
+    ///
+    /// Every operation can be `commit`ed to keep its change, but only commit one that was applied
+    /// directly to the `Vec`. Enclosing operations revert by position rather than by value, so one
+    /// that is still alive undoes the committed change instead of its own. For example, committing
+    /// `b.pushed(3)` inside `let mut b = a.pushed(2)` on `[1]` leaves `[1, 2]` once `b` is reverted:
+    /// the pop takes away the committed 3 instead of the temporary 2. In debug builds with `std`,
+    /// committing beneath a live operation panics instead.
     pub trait VecScoped<T>: VecScopedPrivate<Element = T> {
+        /// Temporarily move all of the elements of `other` onto the end of the `Vec`, leaving
+        /// `other` empty. Afterwards, the elements are moved back into `other`.
+        fn appended<'b>(&'b mut self, other: &'b mut Vec<T>) -> Append<'b, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Append::new(self, other)
+        }
+
         /// Temporarily assign an element at `idx` of the `Vec`.
         /// Panics if `idx` is out of bounds.
-        fn assigned(&mut self, idx: usize, value: T) -> Assign<Self>
+        fn assigned(&mut self, idx: usize, value: T) -> Assign<'_, Self>
         where
             Self: Sized,
         {
             Assign::new(self, value, idx)
         }
 
+        /// Temporarily apply each `(idx, value)` assignment of `updates` in order. If an index
+        /// appears more than once, the last value wins, and the original element is still restored.
+        /// Panics if any index is out of bounds, before anything is assigned.
+        fn assigned_many(&mut self, updates: Vec<(usize, T)>) -> AssignMany<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            AssignMany::new(self, updates)
+        }
+
+        /// Like `assigned`, but if `idx` is out of bounds, temporarily grow the `Vec` with default
+        /// elements up to `idx` and push `value` there instead of panicking. Afterwards, the `Vec`
+        /// is truncated back to its original length.
+        fn assigned_or_extended(&mut self, idx: usize, value: T) -> AssignOrExtend<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: Default,
+        {
+            AssignOrExtend::new(self, idx, value)
+        }
+
+        /// Iterates over the current state of the `Vec` in non-overlapping chunks of `size`
+        /// elements; the last chunk is shorter if `size` doesn't divide the length.
+        /// Panics if `size` is 0.
+        fn chunks(&self, size: usize) -> core::slice::Chunks<'_, T>
+        where
+            Self: VecBacked,
+        {
+            self.vec_ref().as_slice().chunks(size)
+        }
+
+        /// Temporarily reverse each block of `chunk` consecutive elements of the `Vec` in place.
+        /// If `chunk` doesn't divide the length, the shorter trailing block is reversed too.
+        /// Panics if `chunk` is 0.
+        fn chunks_reversed(&mut self, chunk: usize) -> ChunksReversed<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            ChunksReversed::new(self, chunk)
+        }
+
+        /// Returns `true` if the current state of the `Vec` contains an element equal to `x`
+        fn contains(&self, x: &T) -> bool
+        where
+            Self: VecBacked,
+            T: PartialEq,
+        {
+            self.vec_ref().as_slice().contains(x)
+        }
+
+        /// Temporarily remove consecutive repeated elements of the `Vec`
+        fn deduped(&mut self) -> Dedup<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: PartialEq,
+        {
+            Dedup::new(self)
+        }
+
+        /// Returns the number of scoped operations that are currently applied, which is how many
+        /// reverts are still pending. This is 0 for a plain `Vec`.
+        fn depth(&self) -> usize {
+            self.vec_depth()
+        }
+
+        /// Temporarily remove the elements in `range` from the `Vec`, shifting all elements after
+        /// it to the left.
+        /// Panics if the range is out of bounds or its start is after its end.
+        fn drained<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Drain::new(self, range)
+        }
+
+        /// Temporarily append all of the items of `iter` onto the end of the `Vec`
+        fn extended<I: IntoIterator<Item = T>>(&mut self, iter: I) -> Extend<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Extend::new(self, iter)
+        }
+
+        /// Temporarily append clones of all of the elements of `slice` onto the end of the `Vec`
+        fn extended_from_slice(&mut self, slice: &[T]) -> ExtendFromSlice<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: Clone,
+        {
+            ExtendFromSlice::new(self, slice)
+        }
+
+        /// Temporarily overwrite every element of the `Vec` with clones of `value`. This saves the
+        /// original elements to restore afterwards.
+        fn filled(&mut self, value: T) -> Fill<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: Clone,
+        {
+            Fill::new(self, value)
+        }
+
+        /// Returns the first element of the current state of the `Vec`, if there is one
+        fn first(&self) -> Option<&T>
+        where
+            Self: VecBacked,
+        {
+            self.vec_ref().as_slice().first()
+        }
+
+        /// Wrap this in a `Frozen`, which can still be read but can't be changed any further. This
+        /// is useful for handing the current state to code that should only read it. Any scoped
+        /// operations inside are still reverted when the `Frozen` is dropped.
+        fn frozen(self) -> Frozen<Self>
+        where
+            Self: Sized,
+        {
+            Frozen(self)
+        }
+
+        /// Returns the element at `idx` in the current state of the `Vec`, if it's in bounds
+        fn get(&self, idx: usize) -> Option<&T>
+        where
+            Self: VecBacked,
+        {
+            self.vec_ref().as_slice().get(idx)
+        }
+
+        /// Temporarily insert an element at position `idx` of the `Vec`, shifting all elements
+        /// after it to the right.
+        /// Panics if `idx > len`.
+        fn inserted(&mut self, idx: usize, value: T) -> Insert<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Insert::new(self, idx, value)
+        }
+
+        /// Returns `true` if the current state of the `Vec` has no elements
+        fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// Iterates over the current state of the `Vec` from back to front, so the most recently
+        /// pushed element comes first
+        fn iter_rev(&self) -> core::iter::Rev<core::slice::Iter<'_, T>>
+        where
+            Self: VecBacked,
+        {
+            self.vec_ref().iter().rev()
+        }
+
+        /// Returns the last element of the current state of the `Vec`, if there is one
+        fn last(&self) -> Option<&T>
+        where
+            Self: VecBacked,
+        {
+            self.vec_ref().as_slice().last()
+        }
+
+        /// Returns the number of elements in the current state of the `Vec`
+        fn len(&self) -> usize {
+            PushPopStorage::len(self.storage_ref())
+        }
+
+        /// Temporarily replace each element of the `Vec` with the result of calling `f` on it
+        fn mapped<F: Fn(&T) -> T>(&mut self, f: F) -> Map<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Map::new(self, f)
+        }
+
+        /// Like `mapped`, but `f` is also given the index of each element
+        fn mapped_indexed<F: Fn(usize, &T) -> T>(&mut self, f: F) -> MapIndexed<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            MapIndexed::new(self, f)
+        }
+
         /// This can be used to turn a `Vec` into a `VecScoped`
-        fn nooped(&mut self) -> Noop<Self>
+        fn nooped(&mut self) -> Noop<'_, Self>
         where
             Self: Sized,
         {
@@ -137,356 +739,9869 @@ pub mod borrowed {
         }
 
         /// Temporarily pop the last element from the end of the `Vec`
-        fn popped(&mut self) -> Pop<Self>
-        where
-            Self: Sized,
-        {
-            Pop::new(self)
+        fn popped(&mut self) -> Pop<'_, Self>
+        where
+            Self: Sized,
+        {
+            Pop::new(self)
+        }
+
+        /// Temporarily pop the last `n` elements from the end of the `Vec`, or all of them if there
+        /// are fewer than `n`. The popped elements can be read through `PopN::popped_values`.
+        fn popped_n(&mut self, n: usize) -> PopN<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            PopN::new(self, n)
+        }
+
+        /// Temporarily insert an element at the front of the `Vec`, shifting all elements to the
+        /// right. This is the same as `inserted(0, value)`.
+        fn prepended(&mut self, value: T) -> Prepend<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Prepend::new(self, value)
+        }
+
+        /// Temporarily push an element onto the end of the `Vec`
+        fn pushed(&mut self, value: T) -> Push<'_, Self>
+        where
+            Self: Sized,
+        {
+            Push::new(self, value)
+        }
+
+        /// Temporarily push all of `values` onto the end of the `Vec`. Unlike `extended`, the
+        /// number of elements to pop afterwards is known before any of them are pushed.
+        fn pushed_all(&mut self, values: Vec<T>) -> PushAll<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            PushAll::new(self, values)
+        }
+
+        /// Like `pushed`, but first reserve capacity for at least `additional` more elements. The
+        /// revert only pops, so the reserved capacity is kept afterwards. When pushing in a loop,
+        /// reserving once up front like this avoids reallocating on each iteration.
+        fn pushed_with_capacity(&mut self, value: T, additional: usize) -> Push<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            self.vec_mut().reserve(additional);
+            Push::new(self, value)
+        }
+
+        /// Temporarily remove the element at position `idx` of the `Vec`, shifting all elements
+        /// after it to the left.
+        /// Panics if `idx` is out of bounds.
+        fn removed(&mut self, idx: usize) -> Remove<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Remove::new(self, idx)
+        }
+
+        /// Temporarily replace the element at `idx` of the `Vec`, like `assigned`, but the replaced
+        /// element can be read with `taken`, or kept with `into_taken`.
+        /// Panics if `idx` is out of bounds.
+        fn replaced(&mut self, idx: usize, value: T) -> Replace<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Replace::new(self, idx, value)
+        }
+
+        /// Temporarily resize the `Vec` to `new_len` elements, either by appending clones of
+        /// `value` or by dropping the tail.
+        fn resized(&mut self, new_len: usize, value: T) -> Resize<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: Clone,
+        {
+            Resize::new(self, new_len, value)
+        }
+
+        /// Temporarily remove all elements for which `pred` returns `false`, keeping the order of
+        /// the retained elements.
+        fn retained<F: FnMut(&T) -> bool>(&mut self, pred: F) -> Retain<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Retain::new(self, pred)
+        }
+
+        /// Like `retained`, but only elements with an index in `range` can be removed; the rest are
+        /// always kept.
+        /// Panics if `range` is out of bounds.
+        fn retained_in<F: FnMut(&T) -> bool>(
+            &mut self,
+            range: Range<usize>,
+            pred: F,
+        ) -> RetainIn<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            RetainIn::new(self, range, pred)
+        }
+
+        /// Like `retained`, but `f` can also modify the elements that it keeps. This saves a clone
+        /// of the original contents to restore afterwards.
+        fn retained_mut<F: FnMut(&mut T) -> bool>(&mut self, f: F) -> RetainMut<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: Clone,
+        {
+            RetainMut::new(self, f)
+        }
+
+        /// Temporarily reverse the order of the elements of the `Vec`
+        fn reversed(&mut self) -> Reverse<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Reverse::new(self)
+        }
+
+        /// Temporarily rotate the `Vec` in place such that the first `mid` elements move to the
+        /// end.
+        /// Panics if `mid > len`.
+        fn rotated_left(&mut self, mid: usize) -> RotateLeft<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            RotateLeft::new(self, mid)
+        }
+
+        /// Temporarily rotate the `Vec` in place such that the last `k` elements move to the
+        /// front.
+        /// Panics if `k > len`.
+        fn rotated_right(&mut self, k: usize) -> RotateRight<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            RotateRight::new(self, k)
+        }
+
+        /// Returns a copy of the current state of the `Vec`, which isn't reverted along with it
+        fn snapshot(&self) -> Vec<T>
+        where
+            Self: VecBacked,
+            T: Clone,
+        {
+            self.vec_ref().clone()
+        }
+
+        /// Returns the index and previous element of each assignment in the chain that is waiting
+        /// to be restored, most recent first. This covers `assigned` and the operations like it,
+        /// such as `replaced`.
+        fn diff(&self) -> Vec<(usize, &T)> {
+            let mut pending = Vec::new();
+            self.vec_pending_assigns(&mut pending);
+            pending
+        }
+
+        /// Returns the current state of the `Vec` split into two slices at `mid`.
+        /// Panics if `mid > len`.
+        fn split_view(&self, mid: usize) -> (&[T], &[T])
+        where
+            Self: VecBacked,
+        {
+            self.vec_ref().split_at(mid)
+        }
+
+        /// Temporarily sort the `Vec`. This saves a clone of the original contents to restore
+        /// afterwards; see `sorted_tracked` for a version that doesn't need `Clone`.
+        fn sorted(&mut self) -> Sort<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: Ord + Clone,
+        {
+            Sort::new(self)
+        }
+
+        /// Temporarily sort the `Vec` and then remove repeated elements, leaving only the unique
+        /// elements in order. This saves a clone of the original contents to restore afterwards.
+        fn sorted_deduped(&mut self) -> SortDedup<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: Ord + Clone,
+        {
+            SortDedup::new(self)
+        }
+
+        /// Temporarily sort the `Vec`. Instead of cloning the elements, this records where each
+        /// element came from and moves them back afterwards.
+        fn sorted_tracked(&mut self) -> SortTracked<'_, Self>
+        where
+            Self: Sized + VecBacked,
+            T: Ord,
+        {
+            SortTracked::new(self)
+        }
+
+        /// Temporarily replace the elements in `range` with the items of `replacement`, which
+        /// doesn't need to have the same length as the range.
+        /// Panics if the range is out of bounds or its start is after its end.
+        fn spliced<I: IntoIterator<Item = T>>(
+            &mut self,
+            range: Range<usize>,
+            replacement: I,
+        ) -> Splice<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Splice::new(self, range, replacement)
+        }
+
+        /// Temporarily split the `Vec` at `idx`, leaving only the elements before `idx`. Unlike
+        /// `truncated`, the split-off elements can be read through `SplitOff::tail`.
+        /// Panics if `idx > len`.
+        fn split_off_at(&mut self, idx: usize) -> SplitOff<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            SplitOff::new(self, idx)
+        }
+
+        /// Temporarily remove the element at position `idx` of the `Vec`, replacing it with the
+        /// last element. This doesn't preserve ordering, but is O(1).
+        /// Panics if `idx` is out of bounds.
+        fn swap_removed(&mut self, idx: usize) -> SwapRemove<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            SwapRemove::new(self, idx)
+        }
+
+        /// Temporarily swap the elements at positions `i` and `j` of the `Vec`.
+        /// Panics if `i` or `j` is out of bounds.
+        fn swapped(&mut self, i: usize, j: usize) -> Swap<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Swap::new(self, i, j)
+        }
+
+        /// Temporarily shorten the `Vec` to `len` elements. If `len` is greater than or equal to
+        /// the current length, this has no effect.
+        fn truncated(&mut self, len: usize) -> Truncate<'_, Self>
+        where
+            Self: Sized + VecBacked,
+        {
+            Truncate::new(self, len)
+        }
+
+        /// Like `assigned`, but if `idx` is out of bounds, this returns an error containing `value`
+        /// instead of panicking.
+        fn try_assigned(&mut self, idx: usize, value: T) -> Result<Assign<'_, Self>, AssignError<T>>
+        where
+            Self: Sized,
+        {
+            Assign::try_new(self, value, idx)
+        }
+
+        /// Like `inserted`, but if `idx > len`, this returns an error containing `value` instead of
+        /// panicking.
+        fn try_inserted(&mut self, idx: usize, value: T) -> Result<Insert<'_, Self>, InsertError<T>>
+        where
+            Self: Sized + VecBacked,
+        {
+            Insert::try_new(self, idx, value)
+        }
+
+        /// Iterates over all overlapping windows of `size` consecutive elements in the current
+        /// state of the `Vec`.
+        /// Panics if `size` is 0.
+        fn windows(&self, size: usize) -> core::slice::Windows<'_, T>
+        where
+            Self: VecBacked,
+        {
+            self.vec_ref().as_slice().windows(size)
+        }
+
+        /// Run `apply` on the `Vec` right away, and run `undo` on it when the returned `Reverting`
+        /// goes out of scope. This is an escape hatch for operations that this crate doesn't
+        /// provide, so it's up to `undo` to put the `Vec` back exactly the way `apply` found it.
+        fn with_undo<F: FnOnce(&mut Vec<T>)>(
+            &mut self,
+            apply: impl FnOnce(&mut Vec<T>),
+            undo: F,
+        ) -> Reverting<'_, Self, F>
+        where
+            Self: Sized + VecBacked,
+        {
+            Reverting::new(self, apply, undo)
+        }
+    }
+
+    impl<T> PushPopStorage for Vec<T> {
+        type Element = T;
+
+        fn push(&mut self, value: T) {
+            Vec::push(self, value)
+        }
+
+        fn pop(&mut self) -> Option<T> {
+            Vec::pop(self)
+        }
+
+        fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+            self.as_mut_slice().get_mut(idx)
+        }
+
+        fn len(&self) -> usize {
+            Vec::len(self)
+        }
+    }
+
+    impl<S: PushPopStorage> VecScopedPrivate for S {
+        type Element = S::Element;
+        type Storage = S;
+
+        fn storage_ref(&self) -> &S {
+            self
+        }
+
+        fn storage_mut(&mut self) -> &mut S {
+            self
+        }
+
+        fn vec_depth(&self) -> usize {
+            0
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, _pending: &mut Vec<(usize, &'s Self::Element)>) {}
+    }
+
+    impl<T, S: PushPopStorage<Element = T>> VecScoped<T> for S {}
+
+    /// Temporarily move all of the elements of `other` onto `v` while running `f`
+    pub fn with_appended<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        other: &mut Vec<T>,
+        f: impl FnOnce(&mut Append<V>) -> R,
+    ) -> R {
+        f(&mut v.appended(other))
+    }
+
+    /// Temporarily assign an element at `idx` of `v` while running `f`
+    pub fn with_assigned<T, V: VecScoped<T>, R>(
+        v: &mut V,
+        idx: usize,
+        value: T,
+        f: impl FnOnce(&mut Assign<V>) -> R,
+    ) -> R {
+        f(&mut v.assigned(idx, value))
+    }
+
+    /// Temporarily apply each assignment of `updates` to `v` while running `f`
+    pub fn with_assigned_many<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        updates: Vec<(usize, T)>,
+        f: impl FnOnce(&mut AssignMany<V>) -> R,
+    ) -> R {
+        f(&mut v.assigned_many(updates))
+    }
+
+    /// Temporarily assign an element at `idx` of `v`, growing `v` if needed, while running `f`
+    pub fn with_assigned_or_extended<T: Default, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        idx: usize,
+        value: T,
+        f: impl FnOnce(&mut AssignOrExtend<V>) -> R,
+    ) -> R {
+        f(&mut v.assigned_or_extended(idx, value))
+    }
+
+    /// Temporarily reverse each block of `chunk` elements of `v` while running `f`
+    pub fn with_chunks_reversed<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        chunk: usize,
+        f: impl FnOnce(&mut ChunksReversed<V>) -> R,
+    ) -> R {
+        f(&mut v.chunks_reversed(chunk))
+    }
+
+    /// Temporarily remove consecutive repeated elements of `v` while running `f`
+    pub fn with_deduped<T: PartialEq, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        f: impl FnOnce(&mut Dedup<V>) -> R,
+    ) -> R {
+        f(&mut v.deduped())
+    }
+
+    /// Temporarily remove the elements of `v` in `range` while running `f`
+    pub fn with_drained<T, V: VecScoped<T> + VecBacked, B: RangeBounds<usize>, R>(
+        v: &mut V,
+        range: B,
+        f: impl FnOnce(&mut Drain<V>) -> R,
+    ) -> R {
+        f(&mut v.drained(range))
+    }
+
+    /// Temporarily append all of the items of `iter` onto `v` while running `f`
+    pub fn with_extended<T, V: VecScoped<T> + VecBacked, I: IntoIterator<Item = T>, R>(
+        v: &mut V,
+        iter: I,
+        f: impl FnOnce(&mut Extend<V>) -> R,
+    ) -> R {
+        f(&mut v.extended(iter))
+    }
+
+    /// Temporarily append clones of the elements of `slice` onto `v` while running `f`
+    pub fn with_extended_from_slice<T: Clone, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        slice: &[T],
+        f: impl FnOnce(&mut ExtendFromSlice<V>) -> R,
+    ) -> R {
+        f(&mut v.extended_from_slice(slice))
+    }
+
+    /// Temporarily overwrite every element of `v` with `value` while running `f`
+    pub fn with_filled<T: Clone, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        value: T,
+        f: impl FnOnce(&mut Fill<V>) -> R,
+    ) -> R {
+        f(&mut v.filled(value))
+    }
+
+    /// Temporarily insert an element at position `idx` of `v` while running `f`
+    pub fn with_inserted<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        idx: usize,
+        value: T,
+        f: impl FnOnce(&mut Insert<V>) -> R,
+    ) -> R {
+        f(&mut v.inserted(idx, value))
+    }
+
+    /// Temporarily replace each element of `v` with `map(element)` while running `f`
+    pub fn with_mapped<T, V: VecScoped<T> + VecBacked, M: Fn(&T) -> T, R>(
+        v: &mut V,
+        map: M,
+        f: impl FnOnce(&mut Map<V>) -> R,
+    ) -> R {
+        f(&mut v.mapped(map))
+    }
+
+    /// Temporarily replace each element of `v` with `map(index, element)` while running `f`
+    pub fn with_mapped_indexed<T, V: VecScoped<T> + VecBacked, M: Fn(usize, &T) -> T, R>(
+        v: &mut V,
+        map: M,
+        f: impl FnOnce(&mut MapIndexed<V>) -> R,
+    ) -> R {
+        f(&mut v.mapped_indexed(map))
+    }
+
+    /// Run `f` on `v` without modifying it
+    pub fn with_nooped<T, V: VecScoped<T>, R>(v: &mut V, f: impl FnOnce(&mut Noop<V>) -> R) -> R {
+        f(&mut v.nooped())
+    }
+
+    /// Temporarily pop the last element from `v` while running `f`
+    pub fn with_popped<T, V: VecScoped<T>, R>(v: &mut V, f: impl FnOnce(&mut Pop<V>) -> R) -> R {
+        f(&mut v.popped())
+    }
+
+    /// Temporarily pop up to `n` elements from the end of `v` while running `f`
+    pub fn with_popped_n<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        n: usize,
+        f: impl FnOnce(&mut PopN<V>) -> R,
+    ) -> R {
+        f(&mut v.popped_n(n))
+    }
+
+    /// Temporarily insert an element at the front of `v` while running `f`
+    pub fn with_prepended<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        value: T,
+        f: impl FnOnce(&mut Prepend<V>) -> R,
+    ) -> R {
+        f(&mut v.prepended(value))
+    }
+
+    /// Temporarily push an element onto the end of `v` while running `f`
+    pub fn with_pushed<T, V: VecScoped<T>, R>(
+        v: &mut V,
+        value: T,
+        f: impl FnOnce(&mut Push<V>) -> R,
+    ) -> R {
+        f(&mut v.pushed(value))
+    }
+
+    /// Temporarily push all of `values` onto the end of `v` while running `f`
+    pub fn with_pushed_all<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        values: Vec<T>,
+        f: impl FnOnce(&mut PushAll<V>) -> R,
+    ) -> R {
+        f(&mut v.pushed_all(values))
+    }
+
+    /// Temporarily remove the element at position `idx` of `v` while running `f`
+    pub fn with_removed<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        idx: usize,
+        f: impl FnOnce(&mut Remove<V>) -> R,
+    ) -> R {
+        f(&mut v.removed(idx))
+    }
+
+    /// Temporarily replace the element at position `idx` of `v` while running `f`
+    pub fn with_replaced<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        idx: usize,
+        value: T,
+        f: impl FnOnce(&mut Replace<V>) -> R,
+    ) -> R {
+        f(&mut v.replaced(idx, value))
+    }
+
+    /// Temporarily resize `v` to `new_len` elements, padding with `value`, while running `f`
+    pub fn with_resized<T: Clone, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        new_len: usize,
+        value: T,
+        f: impl FnOnce(&mut Resize<V>) -> R,
+    ) -> R {
+        f(&mut v.resized(new_len, value))
+    }
+
+    /// Temporarily remove all elements of `v` failing `pred` while running `f`
+    pub fn with_retained<T, V: VecScoped<T> + VecBacked, P: FnMut(&T) -> bool, R>(
+        v: &mut V,
+        pred: P,
+        f: impl FnOnce(&mut Retain<V>) -> R,
+    ) -> R {
+        f(&mut v.retained(pred))
+    }
+
+    /// Temporarily remove the elements of `v` in `range` failing `pred` while running `f`
+    pub fn with_retained_in<T, V: VecScoped<T> + VecBacked, P: FnMut(&T) -> bool, R>(
+        v: &mut V,
+        range: Range<usize>,
+        pred: P,
+        f: impl FnOnce(&mut RetainIn<V>) -> R,
+    ) -> R {
+        f(&mut v.retained_in(range, pred))
+    }
+
+    /// Temporarily modify the elements of `v`, removing those for which `pred` returns `false`,
+    /// while running `f`
+    pub fn with_retained_mut<T: Clone, V: VecScoped<T> + VecBacked, P: FnMut(&mut T) -> bool, R>(
+        v: &mut V,
+        pred: P,
+        f: impl FnOnce(&mut RetainMut<V>) -> R,
+    ) -> R {
+        f(&mut v.retained_mut(pred))
+    }
+
+    /// Temporarily reverse the order of the elements of `v` while running `f`
+    pub fn with_reversed<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        f: impl FnOnce(&mut Reverse<V>) -> R,
+    ) -> R {
+        f(&mut v.reversed())
+    }
+
+    /// Temporarily rotate `v` left by `mid` while running `f`
+    pub fn with_rotated_left<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        mid: usize,
+        f: impl FnOnce(&mut RotateLeft<V>) -> R,
+    ) -> R {
+        f(&mut v.rotated_left(mid))
+    }
+
+    /// Temporarily rotate `v` right by `k` while running `f`
+    pub fn with_rotated_right<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        k: usize,
+        f: impl FnOnce(&mut RotateRight<V>) -> R,
+    ) -> R {
+        f(&mut v.rotated_right(k))
+    }
+
+    /// Temporarily sort `v` while running `f`
+    pub fn with_sorted<T: Ord + Clone, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        f: impl FnOnce(&mut Sort<V>) -> R,
+    ) -> R {
+        f(&mut v.sorted())
+    }
+
+    /// Temporarily sort and dedup `v` while running `f`
+    pub fn with_sorted_deduped<T: Ord + Clone, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        f: impl FnOnce(&mut SortDedup<V>) -> R,
+    ) -> R {
+        f(&mut v.sorted_deduped())
+    }
+
+    /// Temporarily sort `v` without cloning its elements while running `f`
+    pub fn with_sorted_tracked<T: Ord, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        f: impl FnOnce(&mut SortTracked<V>) -> R,
+    ) -> R {
+        f(&mut v.sorted_tracked())
+    }
+
+    /// Temporarily replace the elements of `v` in `range` with `replacement` while running `f`
+    pub fn with_spliced<T, V: VecScoped<T> + VecBacked, I: IntoIterator<Item = T>, R>(
+        v: &mut V,
+        range: Range<usize>,
+        replacement: I,
+        f: impl FnOnce(&mut Splice<V>) -> R,
+    ) -> R {
+        f(&mut v.spliced(range, replacement))
+    }
+
+    /// Temporarily split `v` at `idx`, keeping only the head, while running `f`
+    pub fn with_split_off_at<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        idx: usize,
+        f: impl FnOnce(&mut SplitOff<V>) -> R,
+    ) -> R {
+        f(&mut v.split_off_at(idx))
+    }
+
+    /// Temporarily swap-remove the element at position `idx` of `v` while running `f`
+    pub fn with_swap_removed<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        idx: usize,
+        f: impl FnOnce(&mut SwapRemove<V>) -> R,
+    ) -> R {
+        f(&mut v.swap_removed(idx))
+    }
+
+    /// Temporarily swap the elements at positions `i` and `j` of `v` while running `f`
+    pub fn with_swapped<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        i: usize,
+        j: usize,
+        f: impl FnOnce(&mut Swap<V>) -> R,
+    ) -> R {
+        f(&mut v.swapped(i, j))
+    }
+
+    /// Temporarily shorten `v` to `len` elements while running `f`
+    pub fn with_truncated<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        len: usize,
+        f: impl FnOnce(&mut Truncate<V>) -> R,
+    ) -> R {
+        f(&mut v.truncated(len))
+    }
+
+    /// Run `body` on `v`, then run `undo` on `v`, even if `body` panics. This is the scoped concept
+    /// for any custom pair of changes, without defining a new type: `body` makes a change and
+    /// `undo` reverts it.
+    pub fn defer_revert<V: ?Sized, U, R>(v: &mut V, undo: U, body: impl FnOnce(&mut V) -> R) -> R
+    where
+        U: FnOnce(&mut V),
+    {
+        let guard = DeferRevert {
+            v,
+            undo: Some(undo),
+        };
+        body(guard.v)
+    }
+
+    /// Runs the undo function of `defer_revert` when dropped, so it also runs while unwinding
+    struct DeferRevert<'a, V: ?Sized, U: FnOnce(&mut V)> {
+        v: &'a mut V,
+        undo: Option<U>,
+    }
+
+    impl<'a, V: ?Sized, U: FnOnce(&mut V)> Drop for DeferRevert<'a, V, U> {
+        fn drop(&mut self) {
+            if let Some(undo) = self.undo.take() {
+                undo(self.v)
+            }
+        }
+    }
+
+    /// What `run_scoped_committed` should do with the changes made by its closure, along with the
+    /// closure's result
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum Outcome<R> {
+        Commit(R),
+        Revert(R),
+    }
+
+    /// Run `build` on a `DynScopedVec` over `v`, reverting everything that it did afterwards
+    pub fn run_scoped<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        build: impl FnOnce(&mut DynScopedVec<T>) -> R,
+    ) -> R {
+        build(&mut DynScopedVec::new(v.vec_mut()))
+    }
+
+    /// Like `run_scoped`, but `build` decides at runtime whether its changes are kept or reverted
+    pub fn run_scoped_committed<T, V: VecScoped<T> + VecBacked, R>(
+        v: &mut V,
+        build: impl FnOnce(&mut DynScopedVec<T>) -> Outcome<R>,
+    ) -> R {
+        let mut scoped = DynScopedVec::new(v.vec_mut());
+        match build(&mut scoped) {
+            Outcome::Commit(result) => {
+                scoped.commit();
+                result
+            }
+            Outcome::Revert(result) => result,
+        }
+    }
+
+    /// A single operation for `apply_all`. Unlike the scoped types, these can be built up in a
+    /// loop, because every list of operations has the same type.
+    #[derive(Clone, Debug, PartialEq)]
+    pub enum Op<T> {
+        Push(T),
+        Pop,
+        Assign(usize, T),
+    }
+
+    /// Undoes a single operation applied by `apply_all`
+    type Revert<'a, T> = Box<dyn FnOnce(&mut Vec<T>) + 'a>;
+
+    /// The reverts for the operations applied so far by `apply_all`, which are run in reverse order
+    /// when this is dropped, even if something panics.
+    struct Reverts<'a, T> {
+        vec: &'a mut Vec<T>,
+        stack: Vec<Revert<'a, T>>,
+    }
+
+    impl<'a, T> Drop for Reverts<'a, T> {
+        fn drop(&mut self) {
+            while let Some(revert) = self.stack.pop() {
+                revert(self.vec)
+            }
+        }
+    }
+
+    /// Temporarily apply each of `ops` to `v` in order while running `f`, then revert them in
+    /// reverse order. A `Pop` of an empty `Vec` does nothing.
+    /// Panics if an `Assign` index is out of bounds, after reverting the operations before it.
+    pub fn apply_all<'a, T: Clone + 'a>(v: &'a mut Vec<T>, ops: &[Op<T>], f: impl FnOnce(&[T])) {
+        let mut reverts = Reverts {
+            vec: v,
+            stack: Vec::with_capacity(ops.len()),
+        };
+        for op in ops {
+            let vec = &mut *reverts.vec;
+            match op {
+                Op::Push(value) => {
+                    vec.push(value.clone());
+                    reverts.stack.push(Box::new(|vec| {
+                        vec.pop();
+                    }));
+                }
+                Op::Pop => {
+                    if let Some(popped) = vec.pop() {
+                        reverts.stack.push(Box::new(move |vec| vec.push(popped)));
+                    }
+                }
+                &Op::Assign(idx, ref value) => {
+                    let len = vec.len();
+                    let slot = vec.get_mut(idx).unwrap_or_else(|| {
+                        panic!("assigned index (is {}) should be < len (is {})", idx, len)
+                    });
+                    let previous = core::mem::replace(slot, value.clone());
+                    reverts.stack.push(Box::new(move |vec| vec[idx] = previous));
+                }
+            }
+        }
+        f(reverts.vec)
+    }
+
+    /// A user-defined operation that knows how to undo itself, for `with_dyn_op`. Because this is
+    /// object safe, different operations can be stored together, like in a
+    /// `Vec<Box<dyn ScopedOp<T>>>`. `revert` is only called after `apply`, and should put the `Vec`
+    /// back exactly the way `apply` found it.
+    pub trait ScopedOp<T> {
+        fn apply(&mut self, v: &mut Vec<T>);
+
+        fn revert(&mut self, v: &mut Vec<T>);
+    }
+
+    /// Reverts a `ScopedOp` when dropped, even if something panics
+    struct RevertOp<'a, 'o, T> {
+        vec: &'a mut Vec<T>,
+        op: &'o mut dyn ScopedOp<T>,
+    }
+
+    impl<'a, 'o, T> Drop for RevertOp<'a, 'o, T> {
+        fn drop(&mut self) {
+            self.op.revert(self.vec)
+        }
+    }
+
+    /// Temporarily apply `op` to `v` while running `body`, then revert it
+    pub fn with_dyn_op<T>(v: &mut Vec<T>, op: &mut dyn ScopedOp<T>, body: impl FnOnce(&[T])) {
+        op.apply(v);
+        let guard = RevertOp { vec: v, op };
+        body(guard.vec)
+    }
+
+    /// How to undo a single operation recorded by a `DynScopedVec`
+    enum Undo<T> {
+        /// Undo a push
+        Pop,
+        /// Undo a pop by pushing the popped element back
+        Push(T),
+        /// Undo an assignment by putting the previous element back at the index
+        Assign(usize, T),
+    }
+
+    /// A point in the history of a `DynScopedVec` that can be rolled back to, from
+    /// `DynScopedVec::checkpoint`
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Checkpoint(usize);
+
+    /// A non-generic alternative to the scoped types, which records each operation on a stack
+    /// instead of in the type. Because the type doesn't change as operations are applied, this can
+    /// be used in loops and recursion. When this is dropped, all of the recorded operations are
+    /// reverted, most recent first.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct DynScopedVec<'a, T> {
+        vec: &'a mut Vec<T>,
+        undo: Vec<Undo<T>>,
+    }
+
+    impl<'a, T> DynScopedVec<'a, T> {
+        pub fn new(vec: &'a mut Vec<T>) -> Self {
+            Self {
+                vec,
+                undo: Vec::new(),
+            }
+        }
+
+        /// Push an element onto the end of the `Vec`, until this is dropped
+        pub fn push(&mut self, value: T) {
+            self.vec.push(value);
+            self.undo.push(Undo::Pop);
+        }
+
+        /// Pop the last element from the end of the `Vec`, until this is dropped. This returns the
+        /// popped element, or `None` if the `Vec` was empty, in which case nothing is recorded.
+        pub fn pop(&mut self) -> Option<&T> {
+            let popped = self.vec.pop()?;
+            self.undo.push(Undo::Push(popped));
+            match self.undo.last() {
+                Some(Undo::Push(popped)) => Some(popped),
+                _ => unreachable!(),
+            }
+        }
+
+        /// Assign an element at `idx` of the `Vec`, until this is dropped.
+        /// Panics if `idx` is out of bounds.
+        pub fn assign(&mut self, idx: usize, value: T) {
+            let len = self.vec.len();
+            let slot = self.vec.get_mut(idx).unwrap_or_else(|| {
+                panic!("assigned index (is {}) should be < len (is {})", idx, len)
+            });
+            let previous = core::mem::replace(slot, value);
+            self.undo.push(Undo::Assign(idx, previous));
+        }
+
+        /// Revert only the most recent recorded operation. Returns `false` if there was nothing
+        /// to revert.
+        pub fn undo_last(&mut self) -> bool {
+            match self.undo.pop() {
+                Some(undo) => {
+                    self.revert(undo);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Mark the current state, so that `rollback_to` can get back to it later
+        pub fn checkpoint(&self) -> Checkpoint {
+            Checkpoint(self.undo.len())
+        }
+
+        /// Revert every operation recorded since `checkpoint`, most recent first. If operations
+        /// from before the checkpoint have already been undone, this does nothing.
+        pub fn rollback_to(&mut self, checkpoint: Checkpoint) {
+            while self.undo.len() > checkpoint.0 {
+                self.undo_last();
+            }
+        }
+
+        /// Make all of the recorded operations permanent: the changes are kept instead of being
+        /// reverted
+        pub fn commit(self) {
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.undo));
+        }
+
+        fn revert(&mut self, undo: Undo<T>) {
+            match undo {
+                Undo::Pop => {
+                    let _did_pop = self.vec.pop().is_some();
+                    debug_assert!(_did_pop, "Someone has illicitly popped an element!");
+                }
+                Undo::Push(value) => self.vec.push(value),
+                Undo::Assign(idx, previous) => self.vec[idx] = previous,
+            }
+        }
+    }
+
+    impl<'a, T> Deref for DynScopedVec<'a, T> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.vec
+        }
+    }
+
+    impl<'a, T: fmt::Debug> fmt::Debug for DynScopedVec<'a, T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("DynScopedVec").field(&&**self).finish()
+        }
+    }
+
+    impl<'a, T> Drop for DynScopedVec<'a, T> {
+        fn drop(&mut self) {
+            while let Some(undo) = self.undo.pop() {
+                self.revert(undo)
+            }
+        }
+    }
+
+    /// Builds up a chain of operations from runtime decisions, such as a list of `Option`s. Each
+    /// `maybe_` method only applies its operation if asked to, and only operations that actually
+    /// ran are recorded. When this is dropped, all of the recorded operations are reverted, most
+    /// recent first.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct ScopedBuilder<'a, T> {
+        inner: DynScopedVec<'a, T>,
+    }
+
+    impl<'a, T> ScopedBuilder<'a, T> {
+        pub fn new(vec: &'a mut Vec<T>) -> Self {
+            Self {
+                inner: DynScopedVec::new(vec),
+            }
+        }
+
+        /// Push `value` onto the end of the `Vec` if it's `Some`, until this is dropped. Returns
+        /// whether anything was pushed.
+        pub fn maybe_push(&mut self, value: Option<T>) -> bool {
+            match value {
+                Some(value) => {
+                    self.inner.push(value);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Pop the last element from the end of the `Vec` if `pop` is `true`, until this is
+        /// dropped. This returns the popped element, or `None` if nothing was popped.
+        pub fn maybe_pop(&mut self, pop: bool) -> Option<&T> {
+            if pop {
+                self.inner.pop()
+            } else {
+                None
+            }
+        }
+
+        /// Assign `value` at `idx` of the `Vec` if it's `Some`, until this is dropped. Returns
+        /// whether anything was assigned.
+        /// Panics if `value` is `Some` and `idx` is out of bounds.
+        pub fn maybe_assign(&mut self, idx: usize, value: Option<T>) -> bool {
+            match value {
+                Some(value) => {
+                    self.inner.assign(idx, value);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        /// Returns the number of operations that actually ran and are waiting to be reverted
+        pub fn recorded(&self) -> usize {
+            self.inner.undo.len()
+        }
+
+        /// Make all of the recorded operations permanent: the changes are kept instead of being
+        /// reverted
+        pub fn commit(self) {
+            self.inner.commit()
+        }
+    }
+
+    impl<'a, T> Deref for ScopedBuilder<'a, T> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<'a, T: fmt::Debug> fmt::Debug for ScopedBuilder<'a, T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("ScopedBuilder").field(&&**self).finish()
+        }
+    }
+
+    /// See `crate::borrowed::VecScoped::frozen`
+    ///
+    /// This derefs to a slice, but it isn't `VecScoped`, so no more operations can be applied.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Frozen<V>(V);
+
+    impl<T, V: Deref<Target = [T]>> Deref for Frozen<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]>> fmt::Debug for Frozen<V> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Frozen").field(&&**self).finish()
+        }
+    }
+
+    /// See `crate::borrowed::VecScoped::appended`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Append<'a, V: VecBacked> {
+        inner: &'a mut V,
+        other: &'a mut Vec<V::Element>,
+        count: usize,
+    }
+
+    impl<'a, V: VecBacked> Append<'a, V> {
+        pub fn new(inner: &'a mut V, other: &'a mut Vec<V::Element>) -> Self {
+            let count = other.len();
+            inner.vec_mut().append(other);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Append);
+            Self {
+                inner,
+                other,
+                count,
+            }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted, so `other`
+        /// stays empty. Only commit an operation that was applied directly to the collection:
+        /// enclosing operations revert by position, so a live one would undo this change instead of
+        /// its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Append<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Append<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Append);
+            generation::reverted(self);
+            let vec = self.inner.vec_mut();
+            let start = vec.len() - self.count;
+            // `other` is borrowed for the whole scope, so it's still empty and the moved elements
+            // can be put back in their original order
+            let mut moved = vec.split_off(start);
+            self.other.append(&mut moved)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Append<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Append<'a, V> {}
+
+    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Assign<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug
+        for Assign<'a, V>
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Assign")
+                .field("vec", &&**self)
+                .field("idx", &self.idx)
+                .field("previous", self.previous())
+                .finish()
+        }
+    }
+
+    /// Edits made through the mutable slice persist past this scope, except at `idx`, which is
+    /// always restored to its previous value.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for Assign<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.inner.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecScopedPrivate> Drop for Assign<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Assign);
+            generation::reverted(self);
+            if let Some(mut previous) = self.previous.take() {
+                let idx = self.idx;
+                let inner = self.inner.storage_mut();
+                if let Some(old) = inner.get_mut(idx) {
+                    core::mem::swap(old, &mut previous);
+                } else {
+                    panic!(
+                        "dropping assigned index (is {}) should be < len (is {}), this should never happen",
+                        idx,
+                        PushPopStorage::len(inner)
+                    )
+                }
+            }
+        }
+    }
+
+    impl<'a, V: VecScopedPrivate> VecScopedPrivate for Assign<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            if let Some(previous) = &self.previous {
+                pending.push((self.idx, previous));
+            }
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Assign<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::assigned_many`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct AssignMany<'a, V: VecBacked> {
+        inner: &'a mut V,
+        /// The original element at each assigned index
+        previous: BTreeMap<usize, V::Element>,
+    }
+
+    impl<'a, V: VecBacked> AssignMany<'a, V> {
+        pub fn new(inner: &'a mut V, updates: Vec<(usize, V::Element)>) -> Self {
+            let vec = inner.vec_mut();
+            let len = vec.len();
+            if let Some(&(idx, _)) = updates.iter().find(|&&(idx, _)| idx >= len) {
+                panic!("assigned index (is {}) should be < len (is {})", idx, len)
+            }
+            let mut previous = BTreeMap::new();
+            for (idx, value) in updates {
+                let replaced = core::mem::replace(&mut vec[idx], value);
+                // Only the first replaced element is original; later ones were assigned here
+                previous.entry(idx).or_insert(replaced);
+            }
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::AssignMany);
+            Self { inner, previous }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.previous));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for AssignMany<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for AssignMany<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::AssignMany);
+            generation::reverted(self);
+            let inner = self.inner.vec_mut();
+            for (idx, previous) in core::mem::take(&mut self.previous) {
+                let len = inner.len();
+                match inner.get_mut(idx) {
+                    Some(slot) => *slot = previous,
+                    None => panic!(
+                        "dropping assigned index (is {}) should be < len (is {}), this should never happen",
+                        idx, len
+                    ),
+                }
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for AssignMany<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            pending.extend(self.previous.iter().map(|(&idx, previous)| (idx, previous)));
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for AssignMany<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::assigned_or_extended`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct AssignOrExtend<'a, V: VecBacked> {
+        inner: &'a mut V,
+        idx: usize,
+        /// The element that was replaced, or `None` if the `Vec` was grown instead
+        previous: Option<V::Element>,
+        len: usize,
+    }
+
+    impl<'a, V: VecBacked> AssignOrExtend<'a, V>
+    where
+        V::Element: Default,
+    {
+        pub fn new(inner: &'a mut V, idx: usize, value: V::Element) -> Self {
+            let vec = inner.vec_mut();
+            let len = vec.len();
+            let previous = if idx < len {
+                Some(core::mem::replace(&mut vec[idx], value))
+            } else {
+                vec.resize_with(idx, Default::default);
+                vec.push(value);
+                None
+            };
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::AssignOrExtend);
+            Self {
+                inner,
+                idx,
+                previous,
+                len,
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> AssignOrExtend<'a, V> {
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(this.previous.take());
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for AssignOrExtend<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for AssignOrExtend<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::AssignOrExtend);
+            generation::reverted(self);
+            let previous = self.previous.take();
+            let (idx, len) = (self.idx, self.len);
+            let vec = self.vec_mut();
+            match previous {
+                Some(previous) => vec[idx] = previous,
+                None => vec.truncate(len),
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for AssignOrExtend<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            if let Some(previous) = &self.previous {
+                pending.push((self.idx, previous));
+            }
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for AssignOrExtend<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::chunks_reversed`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct ChunksReversed<'a, V: VecBacked> {
+        inner: &'a mut V,
+        chunk: usize,
+    }
+
+    impl<'a, V: VecBacked> ChunksReversed<'a, V> {
+        pub fn new(inner: &'a mut V, chunk: usize) -> Self {
+            for block in inner.vec_mut().chunks_mut(chunk) {
+                block.reverse();
+            }
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::ChunksReversed);
+            Self { inner, chunk }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for ChunksReversed<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for ChunksReversed<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::ChunksReversed);
+            generation::reverted(self);
+            // Reversing the same blocks again is its own inverse, as long as the length didn't
+            // change
+            let chunk = self.chunk;
+            for block in self.inner.vec_mut().chunks_mut(chunk) {
+                block.reverse();
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for ChunksReversed<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for ChunksReversed<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::deduped`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Dedup<'a, V: VecBacked> {
+        inner: &'a mut V,
+        /// The removed elements along with their original indices, in increasing order of index
+        removed: Vec<(usize, V::Element)>,
+    }
+
+    impl<'a, V: VecBacked> Dedup<'a, V>
+    where
+        V::Element: PartialEq,
+    {
+        pub fn new(inner: &'a mut V) -> Self {
+            let vec = inner.vec_mut();
+            let original = core::mem::replace(vec, Vec::with_capacity(vec.capacity()));
+            let mut removed = Vec::new();
+            for (idx, value) in original.into_iter().enumerate() {
+                // Like `Vec::dedup`, compare against the last element that was kept
+                if vec.last() == Some(&value) {
+                    removed.push((idx, value));
+                } else {
+                    vec.push(value);
+                }
+            }
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Dedup);
+            Self { inner, removed }
+        }
+    }
+
+    impl<'a, V: VecBacked> Dedup<'a, V> {
+        /// The number of elements that were removed, which will be put back afterwards
+        pub fn removed_count(&self) -> usize {
+            self.removed.len()
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.removed));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Dedup<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Dedup<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Dedup);
+            generation::reverted(self);
+            let removed = core::mem::take(&mut self.removed);
+            let vec = self.vec_mut();
+            let mut kept = core::mem::replace(vec, Vec::with_capacity(vec.capacity())).into_iter();
+            vec.reserve(kept.len() + removed.len());
+            // Merge the two sequences back together, putting each removed element at its
+            // original index
+            for (idx, value) in removed {
+                while vec.len() < idx {
+                    match kept.next() {
+                        Some(k) => vec.push(k),
+                        None => break,
+                    }
+                }
+                vec.push(value);
+            }
+            vec.extend(kept)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Dedup<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Dedup<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::drained`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Drain<'a, V: VecBacked> {
+        inner: &'a mut V,
+        start: usize,
+        drained: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Drain<'a, V> {
+        pub fn new<R: RangeBounds<usize>>(inner: &'a mut V, range: R) -> Self {
+            let start = match range.start_bound() {
+                Bound::Included(&start) => start,
+                Bound::Excluded(&start) => start + 1,
+                Bound::Unbounded => 0,
+            };
+            let drained = inner.vec_mut().drain(range).collect();
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Drain);
+            Self {
+                inner,
+                start,
+                drained,
+            }
+        }
+
+        /// The number of elements that were removed, which will be put back afterwards
+        pub fn removed_count(&self) -> usize {
+            self.drained.len()
+        }
+
+        /// The original index of each element that is still in the `Vec`, in order, for mapping
+        /// current positions back to original ones
+        pub fn surviving_indices(&self) -> Vec<usize> {
+            let end = self.start + self.drained.len();
+            let len = self.inner.vec_ref().len() + self.drained.len();
+            (0..self.start).chain(end..len).collect()
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.drained));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Drain<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Drain<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Drain);
+            generation::reverted(self);
+            let start = self.start;
+            let drained = core::mem::take(&mut self.drained);
+            let vec = self.vec_mut();
+            let in_bounds = start <= vec.len();
+            debug_assert!(in_bounds, "Someone has illicitly removed an element!");
+            if in_bounds {
+                vec.splice(start..start, drained);
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Drain<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Drain<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::extended`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Extend<'a, V: VecBacked> {
+        inner: &'a mut V,
+        count: usize,
+    }
+
+    impl<'a, V: VecBacked> Extend<'a, V> {
+        pub fn new<I: IntoIterator<Item = V::Element>>(inner: &'a mut V, iter: I) -> Self {
+            /// Truncates the new elements away if `iter` panics partway through
+            struct TruncateOnUnwind<'v, T> {
+                vec: &'v mut Vec<T>,
+                len: usize,
+            }
+
+            impl<'v, T> Drop for TruncateOnUnwind<'v, T> {
+                fn drop(&mut self) {
+                    self.vec.truncate(self.len)
+                }
+            }
+
+            let vec = inner.vec_mut();
+            let len_before = vec.len();
+            let guard = TruncateOnUnwind {
+                vec,
+                len: len_before,
+            };
+            guard.vec.extend(iter);
+            let count = guard.vec.len() - len_before;
+            core::mem::forget(guard);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Extend);
+            Self { inner, count }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Extend<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    /// Edits made through the mutable slice persist past this scope. Reverting truncates back to
+    /// the original length, so if elements are reordered, e.g. with `swap`, original elements can
+    /// be truncated away instead of the extended ones.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for Extend<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.inner.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Extend<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Extend);
+            generation::reverted(self);
+            let count = self.count;
+            let inner = self.vec_mut();
+            let mut popped = 0;
+            while popped < count && inner.pop().is_some() {
+                popped += 1;
+            }
+            debug_assert_eq!(popped, count, "Someone has illicitly popped an element!");
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Extend<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Extend<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::extended_from_slice`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct ExtendFromSlice<'a, V: VecBacked> {
+        inner: &'a mut V,
+        count: usize,
+    }
+
+    impl<'a, V: VecBacked> ExtendFromSlice<'a, V>
+    where
+        V::Element: Clone,
+    {
+        pub fn new(inner: &'a mut V, slice: &[V::Element]) -> Self {
+            inner.vec_mut().extend_from_slice(slice);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::ExtendFromSlice);
+            Self {
+                inner,
+                count: slice.len(),
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> ExtendFromSlice<'a, V> {
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for ExtendFromSlice<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for ExtendFromSlice<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::ExtendFromSlice);
+            generation::reverted(self);
+            let count = self.count;
+            let inner = self.vec_mut();
+            debug_assert!(
+                count <= inner.len(),
+                "Someone has illicitly popped an element!"
+            );
+            let len = inner.len().saturating_sub(count);
+            inner.truncate(len)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for ExtendFromSlice<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for ExtendFromSlice<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::filled`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Fill<'a, V: VecBacked> {
+        inner: &'a mut V,
+        original: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Fill<'a, V>
+    where
+        V::Element: Clone,
+    {
+        pub fn new(inner: &'a mut V, value: V::Element) -> Self {
+            let vec = inner.vec_mut();
+            let len = vec.len();
+            let original = core::mem::replace(vec, Vec::with_capacity(vec.capacity()));
+            vec.resize(len, value);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Fill);
+            Self { inner, original }
+        }
+    }
+
+    impl<'a, V: VecBacked> Fill<'a, V> {
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.original));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Fill<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Fill<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Fill);
+            generation::reverted(self);
+            let original = core::mem::take(&mut self.original);
+            let vec = self.vec_mut();
+            debug_assert_eq!(
+                original.len(),
+                vec.len(),
+                "Someone has illicitly changed the length!"
+            );
+            for (slot, value) in vec.iter_mut().zip(original) {
+                *slot = value;
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Fill<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Fill<'a, V> {}
+
+    /// The error returned by `crate::borrowed::VecScoped::try_inserted` when the index is past the
+    /// end. This gives back the value that couldn't be inserted.
+    #[derive(Debug, PartialEq)]
+    pub struct InsertError<T> {
+        pub idx: usize,
+        pub len: usize,
+        pub value: T,
+    }
+
+    impl<T> fmt::Display for InsertError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "insertion index (is {}) should be <= len (is {})",
+                self.idx, self.len
+            )
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: fmt::Debug> std::error::Error for InsertError<T> {}
+
+    /// See `crate::borrowed::VecScoped::inserted`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Insert<'a, V: VecBacked> {
+        inner: &'a mut V,
+        idx: usize,
+    }
+
+    impl<'a, V: VecBacked> Insert<'a, V> {
+        pub fn new(inner: &'a mut V, idx: usize, value: V::Element) -> Self {
+            Self::try_new(inner, idx, value).unwrap_or_else(|err| panic!("{}", err))
+        }
+
+        pub fn try_new(
+            inner: &'a mut V,
+            idx: usize,
+            value: V::Element,
+        ) -> Result<Self, InsertError<V::Element>> {
+            let vec = inner.vec_mut();
+            let len = vec.len();
+            if idx > len {
+                return Err(InsertError { idx, len, value });
+            }
+            vec.insert(idx, value);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Insert);
+            Ok(Self { inner, idx })
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Insert<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    /// Edits made through the mutable slice persist past this scope. Reverting removes whatever is
+    /// at the inserted index by then, so if elements are reordered, e.g. with `swap`, an original
+    /// element can be removed instead of the inserted one.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for Insert<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.inner.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Insert<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Insert);
+            generation::reverted(self);
+            let idx = self.idx;
+            let inner = self.inner.vec_mut();
+            let in_bounds = idx < inner.len();
+            debug_assert!(in_bounds, "Someone has illicitly removed an element!");
+            if in_bounds {
+                inner.remove(idx);
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Insert<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Insert<'a, V> {}
+
+    /// Replaces each element of `vec` with `f(idx, element)`, returning the original elements in
+    /// order. If `f` panics, the elements that were already mapped are put back first.
+    fn map_in_place<T>(vec: &mut [T], mut f: impl FnMut(usize, &T) -> T) -> Vec<T> {
+        struct Restore<'v, T> {
+            vec: &'v mut [T],
+            original: Vec<T>,
+        }
+
+        impl<'v, T> Drop for Restore<'v, T> {
+            fn drop(&mut self) {
+                for (slot, value) in self.vec.iter_mut().zip(self.original.drain(..)) {
+                    *slot = value;
+                }
+            }
+        }
+
+        let original = Vec::with_capacity(vec.len());
+        let mut guard = Restore { vec, original };
+        for idx in 0..guard.vec.len() {
+            let mapped = f(idx, &guard.vec[idx]);
+            let value = core::mem::replace(&mut guard.vec[idx], mapped);
+            guard.original.push(value);
+        }
+        core::mem::take(&mut guard.original)
+    }
+
+    /// See `crate::borrowed::VecScoped::mapped`
+    ///
+    /// The original elements are moved into a second buffer of the same length, which is allocated
+    /// up front and kept for the whole scope.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Map<'a, V: VecBacked> {
+        inner: &'a mut V,
+        original: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Map<'a, V> {
+        pub fn new<F: Fn(&V::Element) -> V::Element>(inner: &'a mut V, f: F) -> Self {
+            let original = map_in_place(inner.vec_mut(), |_, value| f(value));
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Map);
+            Self { inner, original }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.original));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Map<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Map<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Map);
+            generation::reverted(self);
+            let original = core::mem::take(&mut self.original);
+            let vec = self.vec_mut();
+            debug_assert_eq!(
+                original.len(),
+                vec.len(),
+                "Someone has illicitly changed the length!"
+            );
+            for (slot, value) in vec.iter_mut().zip(original) {
+                *slot = value;
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Map<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Map<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::mapped_indexed`
+    ///
+    /// Like `Map`, this moves the original elements into a second buffer rather than cloning them.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct MapIndexed<'a, V: VecBacked> {
+        inner: &'a mut V,
+        original: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> MapIndexed<'a, V> {
+        pub fn new<F: Fn(usize, &V::Element) -> V::Element>(inner: &'a mut V, f: F) -> Self {
+            let original = map_in_place(inner.vec_mut(), f);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::MapIndexed);
+            Self { inner, original }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.original));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for MapIndexed<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for MapIndexed<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::MapIndexed);
+            generation::reverted(self);
+            let original = core::mem::take(&mut self.original);
+            let vec = self.vec_mut();
+            debug_assert_eq!(
+                original.len(),
+                vec.len(),
+                "Someone has illicitly changed the length!"
+            );
+            for (slot, value) in vec.iter_mut().zip(original) {
+                *slot = value;
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for MapIndexed<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for MapIndexed<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::noop`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Noop<'a, V: VecScopedPrivate>(&'a mut V);
+
+    impl<'a, V: VecScopedPrivate> Noop<'a, V> {
+        pub fn new(vec_scoped: &'a mut V) -> Self {
+            generation::applied(vec_scoped);
+            Self(vec_scoped)
+        }
+
+        /// A `Noop` has no change to keep, so this just ends the scope. It exists so that every
+        /// operation can be committed uniformly.
+        pub fn commit(self) {}
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Noop<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.0
+        }
+    }
+
+    /// A `Noop` has nothing to revert, so edits made through the mutable slice simply persist.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for Noop<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.0.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecScopedPrivate> Drop for Noop<'a, V> {
+        fn drop(&mut self) {
+            generation::reverted(self);
+        }
+    }
+
+    impl<'a, V: VecScopedPrivate> VecScopedPrivate for Noop<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.0.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.0.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.0.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.0.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Noop<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::pop`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Pop<'a, V: VecScopedPrivate> {
+        inner: &'a mut V,
+        popped: Option<V::Element>,
+    }
+
+    impl<'a, V: VecScopedPrivate> Pop<'a, V> {
+        pub fn new(inner: &'a mut V) -> Self {
+            let popped = inner.storage_mut().pop();
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Pop);
+            Self { inner, popped }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(this.popped.take());
+        }
+
+        /// The element that was popped and will be pushed back, or `None` if the `Vec` was empty.
+        /// This is not called `popped` so that it doesn't shadow `VecScoped::popped`.
+        pub fn popped_value(&self) -> Option<&V::Element> {
+            self.popped.as_ref()
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Pop<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug
+        for Pop<'a, V>
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Pop")
+                .field("vec", &&**self)
+                .field("popped", &self.popped)
+                .finish()
+        }
+    }
+
+    /// Edits made through the mutable slice persist past this scope; the popped element is still
+    /// pushed back afterwards.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for Pop<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.inner.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecScopedPrivate> Drop for Pop<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Pop);
+            generation::reverted(self);
+            if let Some(popped) = self.popped.take() {
+                self.storage_mut().push(popped)
+            }
+        }
+    }
+
+    impl<'a, V: VecScopedPrivate> VecScopedPrivate for Pop<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Pop<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::popped_n`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct PopN<'a, V: VecBacked> {
+        inner: &'a mut V,
+        popped: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> PopN<'a, V> {
+        pub fn new(inner: &'a mut V, n: usize) -> Self {
+            let vec = inner.vec_mut();
+            let popped = vec.split_off(vec.len().saturating_sub(n));
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::PopN);
+            Self { inner, popped }
+        }
+
+        /// The elements that were popped, in the order that they were in the `Vec`. This can be
+        /// shorter than `n` if the `Vec` didn't have enough elements.
+        pub fn popped_values(&self) -> &[V::Element] {
+            &self.popped
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.popped));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for PopN<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for PopN<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::PopN);
+            generation::reverted(self);
+            let mut popped = core::mem::take(&mut self.popped);
+            self.vec_mut().append(&mut popped)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for PopN<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for PopN<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::prepended`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Prepend<'a, V: VecBacked> {
+        inner: &'a mut V,
+    }
+
+    impl<'a, V: VecBacked> Prepend<'a, V> {
+        pub fn new(inner: &'a mut V, value: V::Element) -> Self {
+            inner.vec_mut().insert(0, value);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Prepend);
+            Self { inner }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Prepend<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    /// Edits made through the mutable slice persist past this scope. Reverting removes whatever is
+    /// at the front by then, so if elements are reordered, e.g. with `swap`, an original element
+    /// can be removed instead of the prepended one.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for Prepend<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.inner.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Prepend<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Prepend);
+            generation::reverted(self);
+            let inner = self.inner.vec_mut();
+            let non_empty = !inner.is_empty();
+            debug_assert!(non_empty, "Someone has illicitly removed an element!");
+            if non_empty {
+                inner.remove(0);
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Prepend<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Prepend<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::push`
+    ///
+    /// Like every scoped operation, this warns if it isn't used, because it would be reverted right
+    /// away.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Push<'a, V: VecScopedPrivate>(&'a mut V);
+
+    impl<'a, V: VecScopedPrivate> Push<'a, V> {
+        pub fn new(vec_scoped: &'a mut V, value: V::Element) -> Self {
+            vec_scoped.storage_mut().push(value);
+            generation::applied(vec_scoped);
+            trace!("applied {:?}", OpKind::Push);
+            Self(vec_scoped)
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Push<'a, V> {
+        /// The element that was pushed, which will be popped when this goes out of scope
+        pub fn pushed_value(&self) -> &T {
+            self.0
+                .last()
+                .expect("a pushed Vec should never be empty, this should never happen")
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Push<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.0
+        }
+    }
+
+    /// Edits made through the mutable slice persist past this scope. Only a slice is exposed rather
+    /// than the `Vec`, so the length can't change, but reverting always pops the last element.
+    /// Reordering through the slice, e.g. with `swap`, loses whichever original element ends up
+    /// last, and leaves the pushed element behind.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for Push<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.0.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecScopedPrivate> Drop for Push<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Push);
+            generation::reverted(self);
+            let _did_pop = self.0.storage_mut().pop().is_some();
+            debug_assert!(_did_pop, "Someone has illicitly popped an element!");
+        }
+    }
+
+    impl<'a, V: VecScopedPrivate> VecScopedPrivate for Push<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.0.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.0.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.0.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.0.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Push<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::pushed_all`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct PushAll<'a, V: VecBacked> {
+        inner: &'a mut V,
+        count: usize,
+    }
+
+    impl<'a, V: VecBacked> PushAll<'a, V> {
+        pub fn new(inner: &'a mut V, mut values: Vec<V::Element>) -> Self {
+            let count = values.len();
+            inner.vec_mut().append(&mut values);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::PushAll);
+            Self { inner, count }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for PushAll<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    /// Edits made through the mutable slice persist past this scope. Reverting pops as many
+    /// elements as were pushed, so if elements are reordered, e.g. with `swap`, original elements
+    /// can be popped instead of the pushed ones.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for PushAll<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.inner.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for PushAll<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::PushAll);
+            generation::reverted(self);
+            let count = self.count;
+            let inner = self.vec_mut();
+            debug_assert!(
+                inner.len() >= count,
+                "Someone has illicitly popped an element!"
+            );
+            let len = inner.len().saturating_sub(count);
+            inner.truncate(len);
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for PushAll<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for PushAll<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::removed`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Remove<'a, V: VecBacked> {
+        inner: &'a mut V,
+        idx: usize,
+        removed: Option<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Remove<'a, V> {
+        pub fn new(inner: &'a mut V, idx: usize) -> Self {
+            let vec = inner.vec_mut();
+            if idx >= vec.len() {
+                panic!(
+                    "removal index (is {}) should be < len (is {})",
+                    idx,
+                    vec.len()
+                )
+            }
+            let removed = Some(vec.remove(idx));
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Remove);
+            Self {
+                inner,
+                idx,
+                removed,
+            }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(this.removed.take());
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Remove<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Remove<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Remove);
+            generation::reverted(self);
+            if let Some(removed) = self.removed.take() {
+                let idx = self.idx;
+                self.vec_mut().insert(idx, removed)
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Remove<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Remove<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::replaced`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Replace<'a, V: VecBacked> {
+        inner: &'a mut V,
+        idx: usize,
+        taken: Option<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Replace<'a, V> {
+        pub fn new(inner: &'a mut V, idx: usize, value: V::Element) -> Self {
+            let vec = inner.vec_mut();
+            let len = vec.len();
+            let taken = match vec.get_mut(idx) {
+                Some(old) => core::mem::replace(old, value),
+                None => panic!("replaced index (is {}) should be < len (is {})", idx, len),
+            };
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Replace);
+            Self {
+                inner,
+                idx,
+                taken: Some(taken),
+            }
+        }
+
+        /// The element that was replaced, which will be put back when this goes out of scope
+        pub fn taken(&self) -> &V::Element {
+            self.taken
+                .as_ref()
+                .expect("the taken value is only removed when the scope ends")
+        }
+
+        /// Keep the replacement and give back the element that it replaced. Like `commit`, only do
+        /// this to an operation that was applied directly to the `Vec`.
+        pub fn into_taken(self) -> V::Element {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            this.taken
+                .take()
+                .expect("the taken value is only removed when the scope ends")
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            drop(self.into_taken());
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Replace<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Replace<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Replace);
+            generation::reverted(self);
+            if let Some(taken) = self.taken.take() {
+                let idx = self.idx;
+                self.vec_mut()[idx] = taken;
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Replace<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            if let Some(previous) = &self.taken {
+                pending.push((self.idx, previous));
+            }
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Replace<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::resized`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Resize<'a, V: VecBacked> {
+        inner: &'a mut V,
+        len: usize,
+        tail: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Resize<'a, V>
+    where
+        V::Element: Clone,
+    {
+        pub fn new(inner: &'a mut V, new_len: usize, value: V::Element) -> Self {
+            let vec = inner.vec_mut();
+            let len = vec.len();
+            let tail = if new_len < len {
+                vec.split_off(new_len)
+            } else {
+                vec.resize(new_len, value);
+                Vec::new()
+            };
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Resize);
+            Self { inner, len, tail }
+        }
+    }
+
+    impl<'a, V: VecBacked> Resize<'a, V> {
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.tail));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Resize<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Resize<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Resize);
+            generation::reverted(self);
+            // Only one of these does anything: growing is undone by truncating and shrinking is
+            // undone by appending the saved tail
+            let len = self.len;
+            let mut tail = core::mem::take(&mut self.tail);
+            let vec = self.vec_mut();
+            vec.truncate(len);
+            vec.append(&mut tail)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Resize<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Resize<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::retained`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Retain<'a, V: VecBacked> {
+        inner: &'a mut V,
+        /// The removed elements along with their original indices, in increasing index order
+        removed: Vec<(usize, V::Element)>,
+    }
+
+    impl<'a, V: VecBacked> Retain<'a, V> {
+        pub fn new<F: FnMut(&V::Element) -> bool>(inner: &'a mut V, mut pred: F) -> Self {
+            let vec = inner.vec_mut();
+            // Run `pred` on everything before moving any elements, so that if it panics, the `Vec`
+            // is left as it was
+            let keep: Vec<bool> = vec.iter().map(&mut pred).collect();
+            let original = core::mem::replace(vec, Vec::with_capacity(vec.capacity()));
+            let mut removed = Vec::new();
+            for ((idx, value), keep) in original.into_iter().enumerate().zip(keep) {
+                if keep {
+                    vec.push(value);
+                } else {
+                    removed.push((idx, value));
+                }
+            }
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Retain);
+            Self { inner, removed }
+        }
+
+        /// The number of elements that were removed, which will be put back afterwards
+        pub fn removed_count(&self) -> usize {
+            self.removed.len()
+        }
+
+        /// The original index of each element that is still in the `Vec`, in order, for mapping
+        /// current positions back to original ones
+        pub fn surviving_indices(&self) -> Vec<usize> {
+            let len = self.inner.vec_ref().len() + self.removed.len();
+            let mut removed = self.removed.iter().map(|&(idx, _)| idx).peekable();
+            (0..len)
+                .filter(|&idx| removed.next_if_eq(&idx).is_none())
+                .collect()
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.removed));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Retain<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Retain<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Retain);
+            generation::reverted(self);
+            let removed = core::mem::take(&mut self.removed);
+            let inner = self.vec_mut();
+            // Inserting in increasing index order means that every element before `idx` is
+            // already in its original position.
+            for (idx, value) in removed {
+                inner.insert(idx, value);
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Retain<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Retain<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::retained_in`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct RetainIn<'a, V: VecBacked> {
+        inner: &'a mut V,
+        /// The removed elements along with their original indices, in increasing index order
+        removed: Vec<(usize, V::Element)>,
+    }
+
+    impl<'a, V: VecBacked> RetainIn<'a, V> {
+        pub fn new<F: FnMut(&V::Element) -> bool>(
+            inner: &'a mut V,
+            range: Range<usize>,
+            mut pred: F,
+        ) -> Self {
+            let vec = inner.vec_mut();
+            if range.start > range.end || range.end > vec.len() {
+                panic!(
+                    "retained range (is {:?}) should be within len (is {})",
+                    range,
+                    vec.len()
+                )
+            }
+            // As in `Retain`, run `pred` before moving anything so that a panic changes nothing
+            let keep: Vec<bool> = vec[range.clone()].iter().map(&mut pred).collect();
+            let mut tail = vec.split_off(range.end);
+            let candidates = vec.split_off(range.start);
+            let mut removed = Vec::new();
+            for ((offset, value), keep) in candidates.into_iter().enumerate().zip(keep) {
+                if keep {
+                    vec.push(value);
+                } else {
+                    removed.push((range.start + offset, value));
+                }
+            }
+            vec.append(&mut tail);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::RetainIn);
+            Self { inner, removed }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.removed));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for RetainIn<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for RetainIn<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::RetainIn);
+            generation::reverted(self);
+            let removed = core::mem::take(&mut self.removed);
+            let inner = self.vec_mut();
+            // As in `Retain`, inserting in increasing index order restores the original positions
+            for (idx, value) in removed {
+                inner.insert(idx, value);
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for RetainIn<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for RetainIn<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::retained_mut`
+    ///
+    /// Since kept elements may have been changed, this can't just reinsert the removed ones like
+    /// `Retain` does. Instead it keeps a clone of the whole original `Vec` for the entire scope.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct RetainMut<'a, V: VecBacked> {
+        inner: &'a mut V,
+        original: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> RetainMut<'a, V>
+    where
+        V::Element: Clone,
+    {
+        pub fn new<F: FnMut(&mut V::Element) -> bool>(inner: &'a mut V, f: F) -> Self {
+            let original = inner.vec_mut().clone();
+            generation::applied(inner);
+            // Build `Self` first, so that if `f` panics partway, dropping it restores the clone
+            let this = Self { inner, original };
+            this.inner.vec_mut().retain_mut(f);
+            trace!("applied {:?}", OpKind::RetainMut);
+            this
+        }
+    }
+
+    impl<'a, V: VecBacked> RetainMut<'a, V> {
+        /// The number of elements that were removed, which will be put back afterwards
+        pub fn removed_count(&self) -> usize {
+            self.original.len() - self.inner.vec_ref().len()
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.original));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for RetainMut<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for RetainMut<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::RetainMut);
+            generation::reverted(self);
+            let mut original = core::mem::take(&mut self.original);
+            let vec = self.vec_mut();
+            vec.clear();
+            vec.append(&mut original)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for RetainMut<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for RetainMut<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::with_undo`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Reverting<'a, V: VecBacked, F: FnOnce(&mut Vec<V::Element>)> {
+        inner: &'a mut V,
+        undo: Option<F>,
+    }
+
+    impl<'a, V: VecBacked, F: FnOnce(&mut Vec<V::Element>)> Reverting<'a, V, F> {
+        pub fn new(inner: &'a mut V, apply: impl FnOnce(&mut Vec<V::Element>), undo: F) -> Self {
+            apply(inner.vec_mut());
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Reverting);
+            Self {
+                inner,
+                undo: Some(undo),
+            }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(this.undo.take());
+        }
+    }
+
+    impl<'a, T, V, F> Deref for Reverting<'a, V, F>
+    where
+        V: Deref<Target = [T]> + VecBacked<Element = T>,
+        F: FnOnce(&mut Vec<T>),
+    {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, T, V, F> fmt::Debug for Reverting<'a, V, F>
+    where
+        T: fmt::Debug,
+        V: Deref<Target = [T]> + VecBacked<Element = T>,
+        F: FnOnce(&mut Vec<T>),
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Reverting").field(&&**self).finish()
+        }
+    }
+
+    impl<'a, V: VecBacked, F: FnOnce(&mut Vec<V::Element>)> Drop for Reverting<'a, V, F> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Reverting);
+            generation::reverted(self);
+            if let Some(undo) = self.undo.take() {
+                undo(self.inner.vec_mut())
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked, F: FnOnce(&mut Vec<V::Element>)> VecScopedPrivate for Reverting<'a, V, F> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V, F> VecScoped<T> for Reverting<'a, V, F>
+    where
+        V: VecBacked<Element = T>,
+        F: FnOnce(&mut Vec<T>),
+    {
+    }
+
+    /// See `crate::borrowed::VecScoped::reversed`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Reverse<'a, V: VecBacked>(&'a mut V);
+
+    impl<'a, V: VecBacked> Reverse<'a, V> {
+        pub fn new(vec_scoped: &'a mut V) -> Self {
+            vec_scoped.vec_mut().reverse();
+            generation::applied(vec_scoped);
+            trace!("applied {:?}", OpKind::Reverse);
+            Self(vec_scoped)
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Reverse<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.0
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Reverse<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Reverse);
+            generation::reverted(self);
+            // Reversing is its own inverse
+            self.0.vec_mut().reverse()
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Reverse<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.0.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.0.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.0.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.0.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Reverse<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::rotated_left`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct RotateLeft<'a, V: VecBacked> {
+        inner: &'a mut V,
+        mid: usize,
+    }
+
+    impl<'a, V: VecBacked> RotateLeft<'a, V> {
+        pub fn new(inner: &'a mut V, mid: usize) -> Self {
+            let vec = inner.vec_mut();
+            if mid > vec.len() {
+                panic!(
+                    "rotation mid (is {}) should be <= len (is {})",
+                    mid,
+                    vec.len()
+                )
+            }
+            vec.rotate_left(mid);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::RotateLeft);
+            Self { inner, mid }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for RotateLeft<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for RotateLeft<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::RotateLeft);
+            generation::reverted(self);
+            let mid = self.mid;
+            self.vec_mut().rotate_right(mid)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for RotateLeft<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for RotateLeft<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::rotated_right`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct RotateRight<'a, V: VecBacked> {
+        inner: &'a mut V,
+        k: usize,
+    }
+
+    impl<'a, V: VecBacked> RotateRight<'a, V> {
+        pub fn new(inner: &'a mut V, k: usize) -> Self {
+            let vec = inner.vec_mut();
+            if k > vec.len() {
+                panic!("rotation k (is {}) should be <= len (is {})", k, vec.len())
+            }
+            vec.rotate_right(k);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::RotateRight);
+            Self { inner, k }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for RotateRight<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for RotateRight<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::RotateRight);
+            generation::reverted(self);
+            let k = self.k;
+            self.vec_mut().rotate_left(k)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for RotateRight<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for RotateRight<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::sorted`
+    ///
+    /// Creating a `Sort` allocates a second buffer holding clones of every element, which is kept
+    /// for the whole scope. The original order is restored by moving those clones back in.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Sort<'a, V: VecBacked> {
+        inner: &'a mut V,
+        original: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Sort<'a, V>
+    where
+        V::Element: Ord + Clone,
+    {
+        pub fn new(inner: &'a mut V) -> Self {
+            let vec = inner.vec_mut();
+            let original = vec.clone();
+            vec.sort();
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Sort);
+            Self { inner, original }
+        }
+    }
+
+    impl<'a, V: VecBacked> Sort<'a, V> {
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.original));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Sort<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Sort<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Sort);
+            generation::reverted(self);
+            let mut original = core::mem::take(&mut self.original);
+            let vec = self.vec_mut();
+            vec.clear();
+            vec.append(&mut original)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Sort<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Sort<'a, V> {}
+
+    /// Rearrange `v` so that the element at each index `i` is the one that was previously at
+    /// `perm[i]`. `perm` must be a permutation of `0..v.len()`.
+    fn permute<T>(v: &mut [T], perm: &[usize]) {
+        let mut done = vec![false; v.len()];
+        for start in 0..v.len() {
+            // Follow the cycle starting here, swapping each element into place
+            let mut i = start;
+            while !done[i] {
+                done[i] = true;
+                let next = perm[i];
+                if next == start {
+                    break;
+                }
+                v.swap(i, next);
+                i = next;
+            }
+        }
+    }
+
+    /// See `crate::borrowed::VecScoped::sorted_deduped`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct SortDedup<'a, V: VecBacked> {
+        inner: &'a mut V,
+        original: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> SortDedup<'a, V>
+    where
+        V::Element: Ord + Clone,
+    {
+        pub fn new(inner: &'a mut V) -> Self {
+            let vec = inner.vec_mut();
+            let original = vec.clone();
+            vec.sort();
+            vec.dedup();
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::SortDedup);
+            Self { inner, original }
+        }
+    }
+
+    impl<'a, V: VecBacked> SortDedup<'a, V> {
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.original));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for SortDedup<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for SortDedup<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::SortDedup);
+            generation::reverted(self);
+            let mut original = core::mem::take(&mut self.original);
+            let vec = self.vec_mut();
+            vec.clear();
+            vec.append(&mut original)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for SortDedup<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for SortDedup<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::sorted_tracked`
+    ///
+    /// Unlike `Sort`, this only allocates a `usize` per element rather than a clone of it.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct SortTracked<'a, V: VecBacked> {
+        inner: &'a mut V,
+        /// The element at index `i` of the sorted `Vec` came from index `perm[i]`
+        perm: Vec<usize>,
+    }
+
+    impl<'a, V: VecBacked> SortTracked<'a, V>
+    where
+        V::Element: Ord,
+    {
+        pub fn new(inner: &'a mut V) -> Self {
+            let vec = inner.vec_mut();
+            let mut perm: Vec<usize> = (0..vec.len()).collect();
+            // This is a stable sort, so it puts equal elements in the same order as `sort` would
+            perm.sort_by(|&i, &j| vec[i].cmp(&vec[j]));
+            permute(vec, &perm);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::SortTracked);
+            Self { inner, perm }
+        }
+    }
+
+    impl<'a, V: VecBacked> SortTracked<'a, V> {
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.perm));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for SortTracked<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for SortTracked<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::SortTracked);
+            generation::reverted(self);
+            let mut inverse = vec![0; self.perm.len()];
+            for (sorted_idx, &original_idx) in self.perm.iter().enumerate() {
+                inverse[original_idx] = sorted_idx;
+            }
+            permute(self.vec_mut(), &inverse)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for SortTracked<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for SortTracked<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::spliced`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Splice<'a, V: VecBacked> {
+        inner: &'a mut V,
+        start: usize,
+        /// How many elements of `replacement` were inserted
+        count: usize,
+        removed: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Splice<'a, V> {
+        pub fn new<I: IntoIterator<Item = V::Element>>(
+            inner: &'a mut V,
+            range: Range<usize>,
+            replacement: I,
+        ) -> Self {
+            // Collect the replacement first, so that if the iterator panics, nothing has changed yet
+            let replacement: Vec<V::Element> = replacement.into_iter().collect();
+            let count = replacement.len();
+            let start = range.start;
+            let removed: Vec<V::Element> = inner.vec_mut().splice(range, replacement).collect();
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Splice);
+            Self {
+                inner,
+                start,
+                count,
+                removed,
+            }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.removed));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Splice<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Splice<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Splice);
+            generation::reverted(self);
+            let (start, count) = (self.start, self.count);
+            let removed = core::mem::take(&mut self.removed);
+            let vec = self.vec_mut();
+            let in_bounds = start + count <= vec.len();
+            debug_assert!(in_bounds, "Someone has illicitly removed an element!");
+            if in_bounds {
+                vec.splice(start..start + count, removed);
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Splice<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Splice<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::split_off_at`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct SplitOff<'a, V: VecBacked> {
+        inner: &'a mut V,
+        tail: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> SplitOff<'a, V> {
+        pub fn new(inner: &'a mut V, idx: usize) -> Self {
+            let vec = inner.vec_mut();
+            if idx > vec.len() {
+                panic!(
+                    "split_off index (is {}) should be <= len (is {})",
+                    idx,
+                    vec.len()
+                )
+            }
+            let tail = vec.split_off(idx);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::SplitOff);
+            Self { inner, tail }
+        }
+
+        /// The elements that were split off, which will be put back at the end of the `Vec`
+        pub fn tail(&self) -> &[V::Element] {
+            &self.tail
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.tail));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for SplitOff<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for SplitOff<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::SplitOff);
+            generation::reverted(self);
+            let mut tail = core::mem::take(&mut self.tail);
+            self.vec_mut().append(&mut tail)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for SplitOff<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for SplitOff<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::swap_removed`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct SwapRemove<'a, V: VecBacked> {
+        inner: &'a mut V,
+        idx: usize,
+        removed: Option<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> SwapRemove<'a, V> {
+        pub fn new(inner: &'a mut V, idx: usize) -> Self {
+            let vec = inner.vec_mut();
+            if idx >= vec.len() {
+                panic!(
+                    "swap_remove index (is {}) should be < len (is {})",
+                    idx,
+                    vec.len()
+                )
+            }
+            let removed = Some(vec.swap_remove(idx));
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::SwapRemove);
+            Self {
+                inner,
+                idx,
+                removed,
+            }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(this.removed.take());
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for SwapRemove<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for SwapRemove<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::SwapRemove);
+            generation::reverted(self);
+            if let Some(removed) = self.removed.take() {
+                let idx = self.idx;
+                let inner = self.vec_mut();
+                // The former last element now lives at `idx` (unless `idx` was the last index).
+                // Pushing the removed element and swapping it into `idx` undoes both moves.
+                inner.push(removed);
+                let last = inner.len() - 1;
+                inner.swap(idx, last)
+            }
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for SwapRemove<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for SwapRemove<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::swapped`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Swap<'a, V: VecBacked> {
+        inner: &'a mut V,
+        i: usize,
+        j: usize,
+    }
+
+    impl<'a, V: VecBacked> Swap<'a, V> {
+        pub fn new(inner: &'a mut V, i: usize, j: usize) -> Self {
+            let vec = inner.vec_mut();
+            for &idx in &[i, j] {
+                if idx >= vec.len() {
+                    panic!(
+                        "swapped index (is {}) should be < len (is {})",
+                        idx,
+                        vec.len()
+                    )
+                }
+            }
+            vec.swap(i, j);
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Swap);
+            Self { inner, i, j }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Swap<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Swap<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Swap);
+            generation::reverted(self);
+            // A swap is its own inverse
+            let (i, j) = (self.i, self.j);
+            self.vec_mut().swap(i, j)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Swap<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Swap<'a, V> {}
+
+    /// See `crate::borrowed::VecScoped::truncated`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Truncate<'a, V: VecBacked> {
+        inner: &'a mut V,
+        tail: Vec<V::Element>,
+    }
+
+    impl<'a, V: VecBacked> Truncate<'a, V> {
+        pub fn new(inner: &'a mut V, len: usize) -> Self {
+            let vec = inner.vec_mut();
+            let tail = if len < vec.len() {
+                vec.split_off(len)
+            } else {
+                Vec::new()
+            };
+            generation::applied(inner);
+            trace!("applied {:?}", OpKind::Truncate);
+            Self { inner, tail }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.tail));
+        }
+    }
+
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked> Deref for Truncate<'a, V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    /// Edits made through the mutable slice persist past this scope; the truncated elements are
+    /// still appended back afterwards.
+    impl<'a, T, V: Deref<Target = [T]> + VecBacked<Element = T>> DerefMut for Truncate<'a, V> {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            self.inner.vec_mut()
+        }
+    }
+
+    impl<'a, V: VecBacked> Drop for Truncate<'a, V> {
+        fn drop(&mut self) {
+            trace!("reverted {:?}", OpKind::Truncate);
+            generation::reverted(self);
+            let mut tail = core::mem::take(&mut self.tail);
+            self.vec_mut().append(&mut tail)
+        }
+    }
+
+    impl<'a, V: VecBacked> VecScopedPrivate for Truncate<'a, V> {
+        type Element = V::Element;
+        type Storage = V::Storage;
+
+        fn storage_ref(&self) -> &Self::Storage {
+            self.inner.storage_ref()
+        }
+
+        fn storage_mut(&mut self) -> &mut Self::Storage {
+            self.inner.storage_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn vec_pending_assigns<'s>(&'s self, pending: &mut Vec<(usize, &'s Self::Element)>) {
+            self.inner.vec_pending_assigns(pending)
+        }
+    }
+
+    impl<'a, T, V: VecBacked<Element = T>> VecScoped<T> for Truncate<'a, V> {}
+
+    /// The kind of a scoped `Vec` operation, as returned by each wrapper's `op_kind`, named after
+    /// the wrapper type
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum OpKind {
+        Append,
+        Assign,
+        AssignMany,
+        AssignOrExtend,
+        ChunksReversed,
+        Dedup,
+        Drain,
+        Extend,
+        ExtendFromSlice,
+        Fill,
+        Insert,
+        Map,
+        MapIndexed,
+        Noop,
+        Pop,
+        PopN,
+        Prepend,
+        Push,
+        PushAll,
+        Remove,
+        Replace,
+        Resize,
+        Retain,
+        RetainIn,
+        RetainMut,
+        Reverse,
+        Reverting,
+        RotateLeft,
+        RotateRight,
+        Sort,
+        SortDedup,
+        SortTracked,
+        Splice,
+        SplitOff,
+        SwapRemove,
+        Swap,
+        Truncate,
+    }
+
+    impl_slice_traits!(VecScopedPrivate: Assign<'a>, Noop<'a>, Pop<'a>, Push<'a>);
+
+    impl_slice_traits!(
+        VecBacked: Append<'a>,
+        AssignMany<'a>,
+        AssignOrExtend<'a>,
+        ChunksReversed<'a>,
+        Dedup<'a>,
+        Drain<'a>,
+        Extend<'a>,
+        ExtendFromSlice<'a>,
+        Fill<'a>,
+        Insert<'a>,
+        Map<'a>,
+        MapIndexed<'a>,
+        PopN<'a>,
+        Prepend<'a>,
+        PushAll<'a>,
+        Remove<'a>,
+        Replace<'a>,
+        Resize<'a>,
+        Retain<'a>,
+        RetainIn<'a>,
+        RetainMut<'a>,
+        Reverse<'a>,
+        RotateLeft<'a>,
+        RotateRight<'a>,
+        Sort<'a>,
+        SortDedup<'a>,
+        SortTracked<'a>,
+        Splice<'a>,
+        SplitOff<'a>,
+        SwapRemove<'a>,
+        Swap<'a>,
+        Truncate<'a>,
+    );
+
+    impl_into_inner!(VecScopedPrivate: Assign.inner, Noop.0, Pop.inner, Push.0);
+
+    impl_into_inner!(
+        VecBacked: Append.inner,
+        AssignMany.inner,
+        AssignOrExtend.inner,
+        ChunksReversed.inner,
+        Dedup.inner,
+        Drain.inner,
+        Extend.inner,
+        ExtendFromSlice.inner,
+        Fill.inner,
+        Insert.inner,
+        Map.inner,
+        MapIndexed.inner,
+        PopN.inner,
+        Prepend.inner,
+        PushAll.inner,
+        Remove.inner,
+        Replace.inner,
+        Resize.inner,
+        Retain.inner,
+        RetainIn.inner,
+        RetainMut.inner,
+        Reverse.0,
+        RotateLeft.inner,
+        RotateRight.inner,
+        Sort.inner,
+        SortDedup.inner,
+        SortTracked.inner,
+        Splice.inner,
+        SplitOff.inner,
+        SwapRemove.inner,
+        Swap.inner,
+        Truncate.inner,
+    );
+
+    impl_debug_via_deref!(VecScopedPrivate: Noop, Push);
+
+    impl_debug_via_deref!(
+        VecBacked: Append,
+        AssignMany,
+        AssignOrExtend,
+        ChunksReversed,
+        Dedup,
+        Drain,
+        Extend,
+        ExtendFromSlice,
+        Fill,
+        Insert,
+        Map,
+        MapIndexed,
+        PopN,
+        Prepend,
+        PushAll,
+        Remove,
+        Replace,
+        Resize,
+        Retain,
+        RetainIn,
+        RetainMut,
+        Reverse,
+        RotateLeft,
+        RotateRight,
+        Sort,
+        SortDedup,
+        SortTracked,
+        Splice,
+        SplitOff,
+        SwapRemove,
+        Swap,
+        Truncate,
+    );
+
+    impl_op_kind!(VecScopedPrivate: Assign, Noop, Pop, Push);
+
+    impl_op_kind!(
+        VecBacked: Append,
+        AssignMany,
+        AssignOrExtend,
+        ChunksReversed,
+        Dedup,
+        Drain,
+        Extend,
+        ExtendFromSlice,
+        Fill,
+        Insert,
+        Map,
+        MapIndexed,
+        PopN,
+        Prepend,
+        PushAll,
+        Remove,
+        Replace,
+        Resize,
+        Retain,
+        RetainIn,
+        RetainMut,
+        Reverse,
+        Reverting<F: FnOnce(&mut Vec<V::Element>)>,
+        RotateLeft,
+        RotateRight,
+        Sort,
+        SortDedup,
+        SortTracked,
+        Splice,
+        SplitOff,
+        SwapRemove,
+        Swap,
+        Truncate,
+    );
+
+    /// The error returned by `crate::borrowed::VecScoped::try_assigned` when the index is out of
+    /// bounds. This gives back the value that couldn't be assigned.
+    #[derive(Debug, PartialEq)]
+    pub struct AssignError<T> {
+        pub idx: usize,
+        pub len: usize,
+        pub value: T,
+    }
+
+    impl<T> fmt::Display for AssignError<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "assigned index (is {}) should be < len (is {})",
+                self.idx, self.len
+            )
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl<T: fmt::Debug> std::error::Error for AssignError<T> {}
+
+    /// See `crate::borrowed::VecScoped::assigned`
+    ///
+    /// The new value is only swapped in after `idx` has been checked, and the old value is stored
+    /// in the returned `Assign` right away. So as soon as the `Vec` has been changed, there's an
+    /// `Assign` that will change it back, even if a panic unwinds through its scope. The old value
+    /// is only taken out by `Drop` or `commit`, so it is never restored twice.
+    ///
+    /// Like every scoped operation, this warns if it isn't used, because it would be reverted right
+    /// away.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Assign<'a, V: VecScopedPrivate> {
+        inner: &'a mut V,
+        idx: usize,
+        previous: Option<V::Element>,
+    }
+
+    impl<'a, V: VecScopedPrivate> Assign<'a, V> {
+        pub fn new(vec_scoped: &'a mut V, value: V::Element, idx: usize) -> Self {
+            Self::try_new(vec_scoped, value, idx).unwrap_or_else(|err| panic!("{}", err))
+        }
+
+        pub fn try_new(
+            vec_scoped: &'a mut V,
+            mut value: V::Element,
+            idx: usize,
+        ) -> Result<Self, AssignError<V::Element>> {
+            let inner = vec_scoped.storage_mut();
+            if let Some(old) = inner.get_mut(idx) {
+                core::mem::swap(old, &mut value);
+            } else {
+                let len = PushPopStorage::len(inner);
+                return Err(AssignError { idx, len, value });
+            }
+            generation::applied(vec_scoped);
+            trace!("applied {:?}", OpKind::Assign);
+            Ok(Self {
+                inner: vec_scoped,
+                idx,
+                previous: Some(value),
+            })
+        }
+
+        /// Assign `f(&previous)` at `idx`, computing the new element from the one it replaces.
+        /// `f` only borrows the previous element, because that's what gets restored afterwards, so
+        /// this doesn't need `Clone`. Panics if `idx` is out of bounds.
+        pub fn replace_with(
+            vec_scoped: &'a mut V,
+            idx: usize,
+            f: impl FnOnce(&V::Element) -> V::Element,
+        ) -> Self {
+            let storage = vec_scoped.storage_mut();
+            let len = PushPopStorage::len(storage);
+            let value = match storage.get_mut(idx) {
+                Some(previous) => f(previous),
+                None => panic!("assigned index (is {}) should be < len (is {})", idx, len),
+            };
+            Self::new(vec_scoped, value, idx)
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            generation::committed(&self);
+            let mut this = ManuallyDrop::new(self);
+            drop(this.previous.take());
+        }
+
+        /// The element that was at `idx` before this assignment, which will be restored when this
+        /// goes out of scope
+        pub fn previous(&self) -> &V::Element {
+            self.previous
+                .as_ref()
+                .expect("the previous value is only taken when the scope ends")
+        }
+    }
+
+    /// Like `VecScopedPrivate`, but for a mutable slice, which can't change length
+    pub trait SliceScopedPrivate {
+        type Element;
+
+        fn slice_ref(&self) -> &[Self::Element];
+
+        fn slice_mut(&mut self) -> &mut [Self::Element];
+    }
+
+    /// This trait represent a mutable slice or a temporary modification of one. Because a slice
+    /// can't grow or shrink, this only has the operations that keep the length the same, which lets
+    /// scoped operations work on arrays and other fixed-size storage.
+    pub trait SliceScoped<T>: SliceScopedPrivate<Element = T> {
+        /// Temporarily assign an element at `idx` of the slice.
+        /// Panics if `idx` is out of bounds.
+        fn assigned(&mut self, idx: usize, value: T) -> SliceAssign<'_, Self>
+        where
+            Self: Sized,
+        {
+            SliceAssign::new(self, idx, value)
+        }
+
+        /// Temporarily reverse the order of the elements of the slice
+        fn reversed(&mut self) -> SliceReverse<'_, Self>
+        where
+            Self: Sized,
+        {
+            SliceReverse::new(self)
+        }
+
+        /// Temporarily sort the slice. This saves a clone of the original contents to restore
+        /// afterwards.
+        fn sorted(&mut self) -> SliceSort<'_, Self>
+        where
+            Self: Sized,
+            T: Ord + Clone,
+        {
+            SliceSort::new(self)
+        }
+
+        /// Temporarily swap the elements at positions `i` and `j` of the slice.
+        /// Panics if `i` or `j` is out of bounds.
+        fn swapped(&mut self, i: usize, j: usize) -> SliceSwap<'_, Self>
+        where
+            Self: Sized,
+        {
+            SliceSwap::new(self, i, j)
+        }
+    }
+
+    impl<T> SliceScopedPrivate for &mut [T] {
+        type Element = T;
+
+        fn slice_ref(&self) -> &[T] {
+            self
+        }
+
+        fn slice_mut(&mut self) -> &mut [T] {
+            self
+        }
+    }
+
+    impl<T> SliceScoped<T> for &mut [T] {}
+
+    /// See `crate::borrowed::SliceScoped::assigned`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct SliceAssign<'a, S: SliceScopedPrivate> {
+        inner: &'a mut S,
+        idx: usize,
+        previous: Option<S::Element>,
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceAssign<'a, S> {
+        pub fn new(inner: &'a mut S, idx: usize, value: S::Element) -> Self {
+            let slice = inner.slice_mut();
+            let len = slice.len();
+            let slot = slice.get_mut(idx).unwrap_or_else(|| {
+                panic!("assigned index (is {}) should be < len (is {})", idx, len)
+            });
+            let previous = Some(core::mem::replace(slot, value));
+            Self {
+                inner,
+                idx,
+                previous,
+            }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            let mut this = ManuallyDrop::new(self);
+            drop(this.previous.take());
+        }
+    }
+
+    impl<'a, T, S: Deref<Target = [T]> + SliceScopedPrivate> Deref for SliceAssign<'a, S> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> Drop for SliceAssign<'a, S> {
+        fn drop(&mut self) {
+            if let Some(previous) = self.previous.take() {
+                let idx = self.idx;
+                self.slice_mut()[idx] = previous;
+            }
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceScopedPrivate for SliceAssign<'a, S> {
+        type Element = S::Element;
+
+        fn slice_ref(&self) -> &[Self::Element] {
+            self.inner.slice_ref()
+        }
+
+        fn slice_mut(&mut self) -> &mut [Self::Element] {
+            self.inner.slice_mut()
+        }
+    }
+
+    impl<'a, T, S: SliceScopedPrivate<Element = T>> SliceScoped<T> for SliceAssign<'a, S> {}
+
+    /// See `crate::borrowed::SliceScoped::reversed`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct SliceReverse<'a, S: SliceScopedPrivate> {
+        inner: &'a mut S,
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceReverse<'a, S> {
+        pub fn new(inner: &'a mut S) -> Self {
+            inner.slice_mut().reverse();
+            Self { inner }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, S: Deref<Target = [T]> + SliceScopedPrivate> Deref for SliceReverse<'a, S> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> Drop for SliceReverse<'a, S> {
+        fn drop(&mut self) {
+            // Reversing is its own inverse
+            self.slice_mut().reverse()
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceScopedPrivate for SliceReverse<'a, S> {
+        type Element = S::Element;
+
+        fn slice_ref(&self) -> &[Self::Element] {
+            self.inner.slice_ref()
+        }
+
+        fn slice_mut(&mut self) -> &mut [Self::Element] {
+            self.inner.slice_mut()
+        }
+    }
+
+    impl<'a, T, S: SliceScopedPrivate<Element = T>> SliceScoped<T> for SliceReverse<'a, S> {}
+
+    /// See `crate::borrowed::SliceScoped::sorted`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct SliceSort<'a, S: SliceScopedPrivate> {
+        inner: &'a mut S,
+        original: Vec<S::Element>,
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceSort<'a, S>
+    where
+        S::Element: Ord + Clone,
+    {
+        pub fn new(inner: &'a mut S) -> Self {
+            let slice = inner.slice_mut();
+            let original = slice.to_vec();
+            slice.sort();
+            Self { inner, original }
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceSort<'a, S> {
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            let mut this = ManuallyDrop::new(self);
+            drop(core::mem::take(&mut this.original));
+        }
+    }
+
+    impl<'a, T, S: Deref<Target = [T]> + SliceScopedPrivate> Deref for SliceSort<'a, S> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> Drop for SliceSort<'a, S> {
+        fn drop(&mut self) {
+            let original = core::mem::take(&mut self.original);
+            for (slot, value) in self.slice_mut().iter_mut().zip(original) {
+                *slot = value;
+            }
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceScopedPrivate for SliceSort<'a, S> {
+        type Element = S::Element;
+
+        fn slice_ref(&self) -> &[Self::Element] {
+            self.inner.slice_ref()
+        }
+
+        fn slice_mut(&mut self) -> &mut [Self::Element] {
+            self.inner.slice_mut()
+        }
+    }
+
+    impl<'a, T, S: SliceScopedPrivate<Element = T>> SliceScoped<T> for SliceSort<'a, S> {}
+
+    /// See `crate::borrowed::SliceScoped::swapped`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct SliceSwap<'a, S: SliceScopedPrivate> {
+        inner: &'a mut S,
+        i: usize,
+        j: usize,
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceSwap<'a, S> {
+        pub fn new(inner: &'a mut S, i: usize, j: usize) -> Self {
+            let slice = inner.slice_mut();
+            for &idx in &[i, j] {
+                if idx >= slice.len() {
+                    panic!(
+                        "swapped index (is {}) should be < len (is {})",
+                        idx,
+                        slice.len()
+                    )
+                }
+            }
+            slice.swap(i, j);
+            Self { inner, i, j }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted. Only commit
+        /// an operation that was applied directly to the collection: enclosing operations revert by
+        /// position, so a live one would undo this change instead of its own.
+        pub fn commit(self) {
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, T, S: Deref<Target = [T]> + SliceScopedPrivate> Deref for SliceSwap<'a, S> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            self.inner
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> Drop for SliceSwap<'a, S> {
+        fn drop(&mut self) {
+            // A swap is its own inverse
+            let (i, j) = (self.i, self.j);
+            self.slice_mut().swap(i, j)
+        }
+    }
+
+    impl<'a, S: SliceScopedPrivate> SliceScopedPrivate for SliceSwap<'a, S> {
+        type Element = S::Element;
+
+        fn slice_ref(&self) -> &[Self::Element] {
+            self.inner.slice_ref()
+        }
+
+        fn slice_mut(&mut self) -> &mut [Self::Element] {
+            self.inner.slice_mut()
+        }
+    }
+
+    impl<'a, T, S: SliceScopedPrivate<Element = T>> SliceScoped<T> for SliceSwap<'a, S> {}
+
+    impl_slice_traits!(
+        SliceScopedPrivate: SliceAssign<'a>,
+        SliceReverse<'a>,
+        SliceSort<'a>,
+        SliceSwap<'a>,
+    );
+
+    impl_debug_via_deref!(
+        SliceScopedPrivate: SliceAssign,
+        SliceReverse,
+        SliceSort,
+        SliceSwap,
+    );
+
+    impl_into_inner!(
+        SliceScopedPrivate: SliceAssign.inner,
+        SliceReverse.inner,
+        SliceSort.inner,
+        SliceSwap.inner,
+    );
+
+    /// Like `VecScopedPrivate`, but for a `VecDeque`
+    pub trait DequeScopedPrivate {
+        type Element;
+
+        fn deque_ref(&self) -> &VecDeque<Self::Element>;
+
+        fn deque_mut(&mut self) -> &mut VecDeque<Self::Element>;
+    }
+
+    /// This trait represent a `VecDeque` or a temporary modification of a `VecDeque`
+    pub trait DequeScoped<T>: DequeScopedPrivate<Element = T> {
+        /// Temporarily pop the last element from the back of the `VecDeque`
+        fn popped_back(&mut self) -> PopBack<'_, Self>
+        where
+            Self: Sized,
+        {
+            PopBack::new(self)
+        }
+
+        /// Temporarily pop the first element from the front of the `VecDeque`
+        fn popped_front(&mut self) -> PopFront<'_, Self>
+        where
+            Self: Sized,
+        {
+            PopFront::new(self)
+        }
+
+        /// Temporarily push an element onto the back of the `VecDeque`
+        fn pushed_back(&mut self, value: T) -> PushBack<'_, Self>
+        where
+            Self: Sized,
+        {
+            PushBack::new(self, value)
+        }
+
+        /// Temporarily push an element onto the front of the `VecDeque`
+        fn pushed_front(&mut self, value: T) -> PushFront<'_, Self>
+        where
+            Self: Sized,
+        {
+            PushFront::new(self, value)
+        }
+    }
+
+    impl<T> DequeScopedPrivate for VecDeque<T> {
+        type Element = T;
+
+        fn deque_ref(&self) -> &VecDeque<Self::Element> {
+            self
+        }
+
+        fn deque_mut(&mut self) -> &mut VecDeque<Self::Element> {
+            self
+        }
+    }
+
+    impl<T> DequeScoped<T> for VecDeque<T> {}
+
+    /// See `crate::borrowed::DequeScoped::popped_back`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct PopBack<'a, D: DequeScopedPrivate> {
+        inner: &'a mut D,
+        popped: Option<D::Element>,
+    }
+
+    impl<'a, D: DequeScopedPrivate> PopBack<'a, D> {
+        pub fn new(inner: &'a mut D) -> Self {
+            let popped = inner.deque_mut().pop_back();
+            Self { inner, popped }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            let mut this = ManuallyDrop::new(self);
+            drop(this.popped.take());
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> Deref for PopBack<'a, D> {
+        type Target = VecDeque<D::Element>;
+
+        fn deref(&self) -> &Self::Target {
+            self.inner.deque_ref()
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> Drop for PopBack<'a, D> {
+        fn drop(&mut self) {
+            if let Some(popped) = self.popped.take() {
+                self.deque_mut().push_back(popped)
+            }
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> DequeScopedPrivate for PopBack<'a, D> {
+        type Element = D::Element;
+
+        fn deque_ref(&self) -> &VecDeque<Self::Element> {
+            self.inner.deque_ref()
+        }
+
+        fn deque_mut(&mut self) -> &mut VecDeque<Self::Element> {
+            self.inner.deque_mut()
+        }
+    }
+
+    impl<'a, T, D: DequeScopedPrivate<Element = T>> DequeScoped<T> for PopBack<'a, D> {}
+
+    /// See `crate::borrowed::DequeScoped::popped_front`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct PopFront<'a, D: DequeScopedPrivate> {
+        inner: &'a mut D,
+        popped: Option<D::Element>,
+    }
+
+    impl<'a, D: DequeScopedPrivate> PopFront<'a, D> {
+        pub fn new(inner: &'a mut D) -> Self {
+            let popped = inner.deque_mut().pop_front();
+            Self { inner, popped }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            let mut this = ManuallyDrop::new(self);
+            drop(this.popped.take());
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> Deref for PopFront<'a, D> {
+        type Target = VecDeque<D::Element>;
+
+        fn deref(&self) -> &Self::Target {
+            self.inner.deque_ref()
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> Drop for PopFront<'a, D> {
+        fn drop(&mut self) {
+            if let Some(popped) = self.popped.take() {
+                self.deque_mut().push_front(popped)
+            }
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> DequeScopedPrivate for PopFront<'a, D> {
+        type Element = D::Element;
+
+        fn deque_ref(&self) -> &VecDeque<Self::Element> {
+            self.inner.deque_ref()
+        }
+
+        fn deque_mut(&mut self) -> &mut VecDeque<Self::Element> {
+            self.inner.deque_mut()
+        }
+    }
+
+    impl<'a, T, D: DequeScopedPrivate<Element = T>> DequeScoped<T> for PopFront<'a, D> {}
+
+    /// See `crate::borrowed::DequeScoped::pushed_back`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct PushBack<'a, D: DequeScopedPrivate>(&'a mut D);
+
+    impl<'a, D: DequeScopedPrivate> PushBack<'a, D> {
+        pub fn new(deque_scoped: &'a mut D, value: D::Element) -> Self {
+            deque_scoped.deque_mut().push_back(value);
+            Self(deque_scoped)
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> Deref for PushBack<'a, D> {
+        type Target = VecDeque<D::Element>;
+
+        fn deref(&self) -> &Self::Target {
+            self.0.deque_ref()
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> Drop for PushBack<'a, D> {
+        fn drop(&mut self) {
+            let _did_pop = self.0.deque_mut().pop_back().is_some();
+            debug_assert!(_did_pop, "Someone has illicitly popped an element!");
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> DequeScopedPrivate for PushBack<'a, D> {
+        type Element = D::Element;
+
+        fn deque_ref(&self) -> &VecDeque<Self::Element> {
+            self.0.deque_ref()
+        }
+
+        fn deque_mut(&mut self) -> &mut VecDeque<Self::Element> {
+            self.0.deque_mut()
+        }
+    }
+
+    impl<'a, T, D: DequeScopedPrivate<Element = T>> DequeScoped<T> for PushBack<'a, D> {}
+
+    /// See `crate::borrowed::DequeScoped::pushed_front`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct PushFront<'a, D: DequeScopedPrivate>(&'a mut D);
+
+    impl<'a, D: DequeScopedPrivate> PushFront<'a, D> {
+        pub fn new(deque_scoped: &'a mut D, value: D::Element) -> Self {
+            deque_scoped.deque_mut().push_front(value);
+            Self(deque_scoped)
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> Deref for PushFront<'a, D> {
+        type Target = VecDeque<D::Element>;
+
+        fn deref(&self) -> &Self::Target {
+            self.0.deque_ref()
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> Drop for PushFront<'a, D> {
+        fn drop(&mut self) {
+            let _did_pop = self.0.deque_mut().pop_front().is_some();
+            debug_assert!(_did_pop, "Someone has illicitly popped an element!");
+        }
+    }
+
+    impl<'a, D: DequeScopedPrivate> DequeScopedPrivate for PushFront<'a, D> {
+        type Element = D::Element;
+
+        fn deque_ref(&self) -> &VecDeque<Self::Element> {
+            self.0.deque_ref()
+        }
+
+        fn deque_mut(&mut self) -> &mut VecDeque<Self::Element> {
+            self.0.deque_mut()
+        }
+    }
+
+    impl<'a, T, D: DequeScopedPrivate<Element = T>> DequeScoped<T> for PushFront<'a, D> {}
+
+    impl_debug_via_deref!(DequeScopedPrivate: PopBack, PopFront, PushBack, PushFront);
+
+    impl_into_inner!(
+        DequeScopedPrivate: PopBack.inner,
+        PopFront.inner,
+        PushBack.0,
+        PushFront.0,
+    );
+
+    /// Like `VecScopedPrivate`, but for a `String`
+    pub trait StringScopedPrivate {
+        fn string_ref(&self) -> &String;
+
+        fn string_mut(&mut self) -> &mut String;
+    }
+
+    /// This trait represent a `String` or a temporary modification of a `String`
+    pub trait StringScoped: StringScopedPrivate {
+        /// Temporarily append a `char` onto the end of the `String`
+        fn pushed_char(&mut self, ch: char) -> PushChar<'_, Self>
+        where
+            Self: Sized,
+        {
+            PushChar::new(self, ch)
+        }
+
+        /// Temporarily append a string slice onto the end of the `String`
+        fn pushed_str(&mut self, s: &str) -> PushStr<'_, Self>
+        where
+            Self: Sized,
+        {
+            PushStr::new(self, s)
+        }
+    }
+
+    impl StringScopedPrivate for String {
+        fn string_ref(&self) -> &String {
+            self
+        }
+
+        fn string_mut(&mut self) -> &mut String {
+            self
+        }
+    }
+
+    impl StringScoped for String {}
+
+    /// See `crate::borrowed::StringScoped::pushed_char`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct PushChar<'a, S: StringScopedPrivate> {
+        inner: &'a mut S,
+        len: usize,
+    }
+
+    impl<'a, S: StringScopedPrivate> PushChar<'a, S> {
+        pub fn new(inner: &'a mut S, ch: char) -> Self {
+            let string = inner.string_mut();
+            let len = string.len();
+            string.push(ch);
+            Self { inner, len }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, S: StringScopedPrivate> Deref for PushChar<'a, S> {
+        type Target = str;
+
+        fn deref(&self) -> &Self::Target {
+            self.inner.string_ref()
+        }
+    }
+
+    impl<'a, S: StringScopedPrivate> Drop for PushChar<'a, S> {
+        fn drop(&mut self) {
+            let len = self.len;
+            self.string_mut().truncate(len)
+        }
+    }
+
+    impl<'a, S: StringScopedPrivate> StringScopedPrivate for PushChar<'a, S> {
+        fn string_ref(&self) -> &String {
+            self.inner.string_ref()
+        }
+
+        fn string_mut(&mut self) -> &mut String {
+            self.inner.string_mut()
+        }
+    }
+
+    impl<'a, S: StringScopedPrivate> StringScoped for PushChar<'a, S> {}
+
+    /// See `crate::borrowed::StringScoped::pushed_str`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct PushStr<'a, S: StringScopedPrivate> {
+        inner: &'a mut S,
+        len: usize,
+    }
+
+    impl<'a, S: StringScopedPrivate> PushStr<'a, S> {
+        pub fn new(inner: &'a mut S, s: &str) -> Self {
+            let string = inner.string_mut();
+            let len = string.len();
+            string.push_str(s);
+            Self { inner, len }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            core::mem::forget(self);
+        }
+    }
+
+    impl<'a, S: StringScopedPrivate> Deref for PushStr<'a, S> {
+        type Target = str;
+
+        fn deref(&self) -> &Self::Target {
+            self.inner.string_ref()
+        }
+    }
+
+    impl<'a, S: StringScopedPrivate> Drop for PushStr<'a, S> {
+        fn drop(&mut self) {
+            let len = self.len;
+            self.string_mut().truncate(len)
+        }
+    }
+
+    impl<'a, S: StringScopedPrivate> StringScopedPrivate for PushStr<'a, S> {
+        fn string_ref(&self) -> &String {
+            self.inner.string_ref()
+        }
+
+        fn string_mut(&mut self) -> &mut String {
+            self.inner.string_mut()
+        }
+    }
+
+    impl<'a, S: StringScopedPrivate> StringScoped for PushStr<'a, S> {}
+
+    impl_debug_via_deref!(StringScopedPrivate: PushChar, PushStr);
+
+    impl_into_inner!(StringScopedPrivate: PushChar.inner, PushStr.inner);
+
+    /// Like `VecScopedPrivate`, but for a `BTreeMap`
+    pub trait BTreeMapScopedPrivate {
+        type Key: Ord;
+        type Value;
+
+        fn btree_map_ref(&self) -> &BTreeMap<Self::Key, Self::Value>;
+
+        fn btree_map_mut(&mut self) -> &mut BTreeMap<Self::Key, Self::Value>;
+    }
+
+    /// This trait represent a `BTreeMap` or a temporary modification of a `BTreeMap`
+    pub trait BTreeMapScoped<K, V>: BTreeMapScopedPrivate<Key = K, Value = V> {
+        /// Temporarily insert a key-value pair into the `BTreeMap`, replacing any existing value
+        /// for that key. The key is cloned so that the entry can be found again to revert it.
+        fn inserted(&mut self, k: K, v: V) -> BTreeMapInsert<'_, Self>
+        where
+            Self: Sized,
+            K: Clone,
+        {
+            BTreeMapInsert::new(self, k, v)
+        }
+
+        /// Temporarily remove a key from the `BTreeMap`. If the key isn't present, this has no
+        /// effect.
+        fn removed(&mut self, k: &K) -> BTreeMapRemove<'_, Self>
+        where
+            Self: Sized,
+        {
+            BTreeMapRemove::new(self, k)
+        }
+    }
+
+    impl<K: Ord, V> BTreeMapScopedPrivate for BTreeMap<K, V> {
+        type Key = K;
+        type Value = V;
+
+        fn btree_map_ref(&self) -> &BTreeMap<Self::Key, Self::Value> {
+            self
+        }
+
+        fn btree_map_mut(&mut self) -> &mut BTreeMap<Self::Key, Self::Value> {
+            self
+        }
+    }
+
+    impl<K: Ord, V> BTreeMapScoped<K, V> for BTreeMap<K, V> {}
+
+    /// See `crate::borrowed::BTreeMapScoped::inserted`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct BTreeMapInsert<'a, M: BTreeMapScopedPrivate> {
+        inner: &'a mut M,
+        key: Option<M::Key>,
+        previous: Option<M::Value>,
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> BTreeMapInsert<'a, M>
+    where
+        M::Key: Clone,
+    {
+        pub fn new(inner: &'a mut M, k: M::Key, v: M::Value) -> Self {
+            let previous = inner.btree_map_mut().insert(k.clone(), v);
+            Self {
+                inner,
+                key: Some(k),
+                previous,
+            }
+        }
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> BTreeMapInsert<'a, M> {
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            let mut this = ManuallyDrop::new(self);
+            drop(this.key.take());
+            drop(this.previous.take());
+        }
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> Deref for BTreeMapInsert<'a, M> {
+        type Target = BTreeMap<M::Key, M::Value>;
+
+        fn deref(&self) -> &Self::Target {
+            self.inner.btree_map_ref()
+        }
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> Drop for BTreeMapInsert<'a, M> {
+        fn drop(&mut self) {
+            if let Some(key) = self.key.take() {
+                let previous = self.previous.take();
+                let map = self.btree_map_mut();
+                match previous {
+                    Some(previous) => map.insert(key, previous),
+                    None => map.remove(&key),
+                };
+            }
+        }
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> BTreeMapScopedPrivate for BTreeMapInsert<'a, M> {
+        type Key = M::Key;
+        type Value = M::Value;
+
+        fn btree_map_ref(&self) -> &BTreeMap<Self::Key, Self::Value> {
+            self.inner.btree_map_ref()
+        }
+
+        fn btree_map_mut(&mut self) -> &mut BTreeMap<Self::Key, Self::Value> {
+            self.inner.btree_map_mut()
+        }
+    }
+
+    impl<'a, K, V, M: BTreeMapScopedPrivate<Key = K, Value = V>> BTreeMapScoped<K, V>
+        for BTreeMapInsert<'a, M>
+    {
+    }
+
+    /// See `crate::borrowed::BTreeMapScoped::removed`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct BTreeMapRemove<'a, M: BTreeMapScopedPrivate> {
+        inner: &'a mut M,
+        removed: Option<(M::Key, M::Value)>,
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> BTreeMapRemove<'a, M> {
+        pub fn new(inner: &'a mut M, k: &M::Key) -> Self {
+            let removed = inner.btree_map_mut().remove_entry(k);
+            Self { inner, removed }
+        }
+
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            let mut this = ManuallyDrop::new(self);
+            drop(this.removed.take());
+        }
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> Deref for BTreeMapRemove<'a, M> {
+        type Target = BTreeMap<M::Key, M::Value>;
+
+        fn deref(&self) -> &Self::Target {
+            self.inner.btree_map_ref()
+        }
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> Drop for BTreeMapRemove<'a, M> {
+        fn drop(&mut self) {
+            if let Some((key, value)) = self.removed.take() {
+                self.btree_map_mut().insert(key, value);
+            }
+        }
+    }
+
+    impl<'a, M: BTreeMapScopedPrivate> BTreeMapScopedPrivate for BTreeMapRemove<'a, M> {
+        type Key = M::Key;
+        type Value = M::Value;
+
+        fn btree_map_ref(&self) -> &BTreeMap<Self::Key, Self::Value> {
+            self.inner.btree_map_ref()
+        }
+
+        fn btree_map_mut(&mut self) -> &mut BTreeMap<Self::Key, Self::Value> {
+            self.inner.btree_map_mut()
+        }
+    }
+
+    impl<'a, K, V, M: BTreeMapScopedPrivate<Key = K, Value = V>> BTreeMapScoped<K, V>
+        for BTreeMapRemove<'a, M>
+    {
+    }
+
+    impl_debug_via_deref!(BTreeMapScopedPrivate: BTreeMapInsert, BTreeMapRemove);
+
+    impl_into_inner!(BTreeMapScopedPrivate: BTreeMapInsert.inner, BTreeMapRemove.inner);
+
+    /// Like `VecScopedPrivate`, but for a `BinaryHeap`
+    pub trait HeapScopedPrivate {
+        type Element: Ord;
+
+        fn heap_ref(&self) -> &BinaryHeap<Self::Element>;
+
+        fn heap_mut(&mut self) -> &mut BinaryHeap<Self::Element>;
+    }
+
+    /// This trait represent a `BinaryHeap` or a temporary modification of a `BinaryHeap`
+    pub trait HeapScoped<T>: HeapScopedPrivate<Element = T> {
+        /// Temporarily push an element onto the `BinaryHeap`. A `BinaryHeap` can't remove an
+        /// arbitrary element, and pushing may move other elements around, so this saves a clone of
+        /// the whole heap to restore afterwards. That makes it O(n) rather than O(log n).
+        fn pushed(&mut self, value: T) -> HeapPush<'_, Self>
+        where
+            Self: Sized,
+            T: Clone,
+        {
+            HeapPush::new(self, value)
+        }
+    }
+
+    impl<T: Ord> HeapScopedPrivate for BinaryHeap<T> {
+        type Element = T;
+
+        fn heap_ref(&self) -> &BinaryHeap<Self::Element> {
+            self
+        }
+
+        fn heap_mut(&mut self) -> &mut BinaryHeap<Self::Element> {
+            self
+        }
+    }
+
+    impl<T: Ord> HeapScoped<T> for BinaryHeap<T> {}
+
+    /// See `crate::borrowed::HeapScoped::pushed`
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct HeapPush<'a, H: HeapScopedPrivate> {
+        inner: &'a mut H,
+        original: Option<BinaryHeap<H::Element>>,
+    }
+
+    impl<'a, H: HeapScopedPrivate> HeapPush<'a, H>
+    where
+        H::Element: Clone,
+    {
+        pub fn new(inner: &'a mut H, value: H::Element) -> Self {
+            let heap = inner.heap_mut();
+            let original = heap.clone();
+            heap.push(value);
+            Self {
+                inner,
+                original: Some(original),
+            }
+        }
+    }
+
+    impl<'a, H: HeapScopedPrivate> HeapPush<'a, H> {
+        /// Make this operation permanent: the change is kept instead of being reverted
+        pub fn commit(self) {
+            let mut this = ManuallyDrop::new(self);
+            drop(this.original.take());
+        }
+    }
+
+    impl<'a, H: HeapScopedPrivate> Deref for HeapPush<'a, H> {
+        type Target = BinaryHeap<H::Element>;
+
+        fn deref(&self) -> &Self::Target {
+            self.inner.heap_ref()
+        }
+    }
+
+    impl<'a, H: HeapScopedPrivate> Drop for HeapPush<'a, H> {
+        fn drop(&mut self) {
+            if let Some(original) = self.original.take() {
+                // Reuse the current buffer so the capacity isn't shrunk. The original elements are
+                // already a valid heap, so rebuilding it doesn't move them.
+                let heap = self.heap_mut();
+                let mut vec = core::mem::take(heap).into_vec();
+                vec.clear();
+                vec.append(&mut original.into_vec());
+                *heap = BinaryHeap::from(vec);
+            }
+        }
+    }
+
+    impl<'a, H: HeapScopedPrivate> HeapScopedPrivate for HeapPush<'a, H> {
+        type Element = H::Element;
+
+        fn heap_ref(&self) -> &BinaryHeap<Self::Element> {
+            self.inner.heap_ref()
+        }
+
+        fn heap_mut(&mut self) -> &mut BinaryHeap<Self::Element> {
+            self.inner.heap_mut()
+        }
+    }
+
+    impl<'a, T, H: HeapScopedPrivate<Element = T>> HeapScoped<T> for HeapPush<'a, H> {}
+
+    impl_debug_via_deref!(HeapScopedPrivate: HeapPush);
+
+    impl_into_inner!(HeapScopedPrivate: HeapPush.inner);
+
+    /// `HashMap` and `HashSet` aren't available without `std`
+    #[cfg(feature = "std")]
+    mod std_collections {
+        use super::*;
+        use core::hash::Hash;
+        use std::collections::{HashMap, HashSet};
+
+        /// Like `VecScopedPrivate`, but for a `HashMap`
+        pub trait MapScopedPrivate {
+            type Key: Eq + Hash;
+            type Value;
+
+            fn map_ref(&self) -> &HashMap<Self::Key, Self::Value>;
+
+            fn map_mut(&mut self) -> &mut HashMap<Self::Key, Self::Value>;
+        }
+
+        /// This trait represent a `HashMap` or a temporary modification of a `HashMap`
+        pub trait MapScoped<K, V>: MapScopedPrivate<Key = K, Value = V> {
+            /// Temporarily insert a key-value pair into the `HashMap`, replacing any existing value for
+            /// that key. The key is cloned so that the entry can be found again to revert it.
+            fn inserted(&mut self, k: K, v: V) -> MapInsert<'_, Self>
+            where
+                Self: Sized,
+                K: Clone,
+            {
+                MapInsert::new(self, k, v)
+            }
+
+            /// Temporarily remove a key from the `HashMap`. If the key isn't present, this has no
+            /// effect.
+            fn removed(&mut self, k: &K) -> MapRemove<'_, Self>
+            where
+                Self: Sized,
+            {
+                MapRemove::new(self, k)
+            }
+        }
+
+        impl<K: Eq + Hash, V> MapScopedPrivate for HashMap<K, V> {
+            type Key = K;
+            type Value = V;
+
+            fn map_ref(&self) -> &HashMap<Self::Key, Self::Value> {
+                self
+            }
+
+            fn map_mut(&mut self) -> &mut HashMap<Self::Key, Self::Value> {
+                self
+            }
+        }
+
+        impl<K: Eq + Hash, V> MapScoped<K, V> for HashMap<K, V> {}
+
+        /// See `crate::borrowed::MapScoped::inserted`
+        #[must_use = "this scoped operation reverts immediately if not bound"]
+        pub struct MapInsert<'a, M: MapScopedPrivate> {
+            inner: &'a mut M,
+            key: Option<M::Key>,
+            previous: Option<M::Value>,
+        }
+
+        impl<'a, M: MapScopedPrivate> MapInsert<'a, M>
+        where
+            M::Key: Clone,
+        {
+            pub fn new(inner: &'a mut M, k: M::Key, v: M::Value) -> Self {
+                let previous = inner.map_mut().insert(k.clone(), v);
+                Self {
+                    inner,
+                    key: Some(k),
+                    previous,
+                }
+            }
+        }
+
+        impl<'a, M: MapScopedPrivate> MapInsert<'a, M> {
+            /// Make this operation permanent: the change is kept instead of being reverted
+            pub fn commit(self) {
+                let mut this = ManuallyDrop::new(self);
+                drop(this.key.take());
+                drop(this.previous.take());
+            }
+        }
+
+        impl<'a, M: MapScopedPrivate> Deref for MapInsert<'a, M> {
+            type Target = HashMap<M::Key, M::Value>;
+
+            fn deref(&self) -> &Self::Target {
+                self.inner.map_ref()
+            }
+        }
+
+        impl<'a, M: MapScopedPrivate> Drop for MapInsert<'a, M> {
+            fn drop(&mut self) {
+                if let Some(key) = self.key.take() {
+                    let previous = self.previous.take();
+                    let map = self.map_mut();
+                    match previous {
+                        Some(previous) => map.insert(key, previous),
+                        None => map.remove(&key),
+                    };
+                }
+            }
+        }
+
+        impl<'a, M: MapScopedPrivate> MapScopedPrivate for MapInsert<'a, M> {
+            type Key = M::Key;
+            type Value = M::Value;
+
+            fn map_ref(&self) -> &HashMap<Self::Key, Self::Value> {
+                self.inner.map_ref()
+            }
+
+            fn map_mut(&mut self) -> &mut HashMap<Self::Key, Self::Value> {
+                self.inner.map_mut()
+            }
+        }
+
+        impl<'a, K, V, M: MapScopedPrivate<Key = K, Value = V>> MapScoped<K, V> for MapInsert<'a, M> {}
+
+        /// See `crate::borrowed::MapScoped::removed`
+        #[must_use = "this scoped operation reverts immediately if not bound"]
+        pub struct MapRemove<'a, M: MapScopedPrivate> {
+            inner: &'a mut M,
+            removed: Option<(M::Key, M::Value)>,
+        }
+
+        impl<'a, M: MapScopedPrivate> MapRemove<'a, M> {
+            pub fn new(inner: &'a mut M, k: &M::Key) -> Self {
+                let removed = inner.map_mut().remove_entry(k);
+                Self { inner, removed }
+            }
+
+            /// Make this operation permanent: the change is kept instead of being reverted
+            pub fn commit(self) {
+                let mut this = ManuallyDrop::new(self);
+                drop(this.removed.take());
+            }
+        }
+
+        impl<'a, M: MapScopedPrivate> Deref for MapRemove<'a, M> {
+            type Target = HashMap<M::Key, M::Value>;
+
+            fn deref(&self) -> &Self::Target {
+                self.inner.map_ref()
+            }
+        }
+
+        impl<'a, M: MapScopedPrivate> Drop for MapRemove<'a, M> {
+            fn drop(&mut self) {
+                if let Some((key, value)) = self.removed.take() {
+                    self.map_mut().insert(key, value);
+                }
+            }
+        }
+
+        impl<'a, M: MapScopedPrivate> MapScopedPrivate for MapRemove<'a, M> {
+            type Key = M::Key;
+            type Value = M::Value;
+
+            fn map_ref(&self) -> &HashMap<Self::Key, Self::Value> {
+                self.inner.map_ref()
+            }
+
+            fn map_mut(&mut self) -> &mut HashMap<Self::Key, Self::Value> {
+                self.inner.map_mut()
+            }
+        }
+
+        impl<'a, K, V, M: MapScopedPrivate<Key = K, Value = V>> MapScoped<K, V> for MapRemove<'a, M> {}
+
+        impl_debug_via_deref!(MapScopedPrivate: MapInsert, MapRemove);
+
+        impl_into_inner!(MapScopedPrivate: MapInsert.inner, MapRemove.inner);
+
+        /// Like `VecScopedPrivate`, but for a `HashSet`
+        pub trait SetScopedPrivate {
+            type Element: Eq + Hash;
+
+            fn set_ref(&self) -> &HashSet<Self::Element>;
+
+            fn set_mut(&mut self) -> &mut HashSet<Self::Element>;
+        }
+
+        /// This trait represent a `HashSet` or a temporary modification of a `HashSet`
+        pub trait SetScoped<T>: SetScopedPrivate<Element = T> {
+            /// Temporarily insert a value into the `HashSet`. If the value was already present, this
+            /// has no effect. The value is cloned so that it can be found again to revert it.
+            fn inserted(&mut self, value: T) -> SetInsert<'_, Self>
+            where
+                Self: Sized,
+                T: Clone,
+            {
+                SetInsert::new(self, value)
+            }
+
+            /// Temporarily remove a value from the `HashSet`. If the value isn't present, this has no
+            /// effect.
+            fn removed(&mut self, value: &T) -> SetRemove<'_, Self>
+            where
+                Self: Sized,
+            {
+                SetRemove::new(self, value)
+            }
+        }
+
+        impl<T: Eq + Hash> SetScopedPrivate for HashSet<T> {
+            type Element = T;
+
+            fn set_ref(&self) -> &HashSet<Self::Element> {
+                self
+            }
+
+            fn set_mut(&mut self) -> &mut HashSet<Self::Element> {
+                self
+            }
+        }
+
+        impl<T: Eq + Hash> SetScoped<T> for HashSet<T> {}
+
+        /// See `crate::borrowed::SetScoped::inserted`
+        #[must_use = "this scoped operation reverts immediately if not bound"]
+        pub struct SetInsert<'a, S: SetScopedPrivate> {
+            inner: &'a mut S,
+            /// The inserted value, only if it wasn't already present
+            inserted: Option<S::Element>,
+        }
+
+        impl<'a, S: SetScopedPrivate> SetInsert<'a, S>
+        where
+            S::Element: Clone,
+        {
+            pub fn new(inner: &'a mut S, value: S::Element) -> Self {
+                let set = inner.set_mut();
+                let inserted = if set.contains(&value) {
+                    None
+                } else {
+                    set.insert(value.clone());
+                    Some(value)
+                };
+                Self { inner, inserted }
+            }
+        }
+
+        impl<'a, S: SetScopedPrivate> SetInsert<'a, S> {
+            /// Make this operation permanent: the change is kept instead of being reverted
+            pub fn commit(self) {
+                let mut this = ManuallyDrop::new(self);
+                drop(this.inserted.take());
+            }
+        }
+
+        impl<'a, S: SetScopedPrivate> Deref for SetInsert<'a, S> {
+            type Target = HashSet<S::Element>;
+
+            fn deref(&self) -> &Self::Target {
+                self.inner.set_ref()
+            }
+        }
+
+        impl<'a, S: SetScopedPrivate> Drop for SetInsert<'a, S> {
+            fn drop(&mut self) {
+                if let Some(inserted) = self.inserted.take() {
+                    self.set_mut().remove(&inserted);
+                }
+            }
+        }
+
+        impl<'a, S: SetScopedPrivate> SetScopedPrivate for SetInsert<'a, S> {
+            type Element = S::Element;
+
+            fn set_ref(&self) -> &HashSet<Self::Element> {
+                self.inner.set_ref()
+            }
+
+            fn set_mut(&mut self) -> &mut HashSet<Self::Element> {
+                self.inner.set_mut()
+            }
+        }
+
+        impl<'a, T, S: SetScopedPrivate<Element = T>> SetScoped<T> for SetInsert<'a, S> {}
+
+        /// See `crate::borrowed::SetScoped::removed`
+        #[must_use = "this scoped operation reverts immediately if not bound"]
+        pub struct SetRemove<'a, S: SetScopedPrivate> {
+            inner: &'a mut S,
+            removed: Option<S::Element>,
+        }
+
+        impl<'a, S: SetScopedPrivate> SetRemove<'a, S> {
+            pub fn new(inner: &'a mut S, value: &S::Element) -> Self {
+                let removed = inner.set_mut().take(value);
+                Self { inner, removed }
+            }
+
+            /// Make this operation permanent: the change is kept instead of being reverted
+            pub fn commit(self) {
+                let mut this = ManuallyDrop::new(self);
+                drop(this.removed.take());
+            }
+        }
+
+        impl<'a, S: SetScopedPrivate> Deref for SetRemove<'a, S> {
+            type Target = HashSet<S::Element>;
+
+            fn deref(&self) -> &Self::Target {
+                self.inner.set_ref()
+            }
+        }
+
+        impl<'a, S: SetScopedPrivate> Drop for SetRemove<'a, S> {
+            fn drop(&mut self) {
+                if let Some(removed) = self.removed.take() {
+                    self.set_mut().insert(removed);
+                }
+            }
+        }
+
+        impl<'a, S: SetScopedPrivate> SetScopedPrivate for SetRemove<'a, S> {
+            type Element = S::Element;
+
+            fn set_ref(&self) -> &HashSet<Self::Element> {
+                self.inner.set_ref()
+            }
+
+            fn set_mut(&mut self) -> &mut HashSet<Self::Element> {
+                self.inner.set_mut()
+            }
+        }
+
+        impl<'a, T, S: SetScopedPrivate<Element = T>> SetScoped<T> for SetRemove<'a, S> {}
+
+        impl_debug_via_deref!(SetScopedPrivate: SetInsert, SetRemove);
+
+        impl_into_inner!(SetScopedPrivate: SetInsert.inner, SetRemove.inner);
+
+        #[test]
+        fn test_map_inserted_new() {
+            let mut a: HashMap<&str, i32> = vec![("a", 1)].into_iter().collect();
+            let original = a.clone();
+            {
+                let b = a.inserted("b", 2);
+                assert_eq!(Some(&2), b.get("b"));
+                assert_eq!(2, b.len());
+            }
+            assert_eq!(original, a);
+        }
+
+        #[test]
+        fn test_map_inserted_overwrite() {
+            let mut a: HashMap<&str, i32> = vec![("a", 1), ("b", 2)].into_iter().collect();
+            let original = a.clone();
+            {
+                let mut b = a.inserted("a", 10);
+                assert_eq!(Some(&10), b.get("a"));
+                assert_eq!(Some(&20), b.inserted("a", 20).get("a"));
+                assert_eq!(Some(&10), b.get("a"));
+            }
+            assert_eq!(original, a);
+        }
+
+        #[test]
+        fn test_map_removed() {
+            let mut a: HashMap<&str, i32> = vec![("a", 1), ("b", 2)].into_iter().collect();
+            let original = a.clone();
+            {
+                let mut b = a.removed(&"a");
+                assert!(!b.contains_key("a"));
+                assert_eq!(1, b.removed(&"missing").len());
+            }
+            assert_eq!(original, a);
+        }
+
+        #[test]
+        fn test_set_inserted_new() {
+            let mut a: HashSet<i32> = vec![1, 2].into_iter().collect();
+            {
+                let b = a.inserted(3);
+                assert!(b.contains(&3));
+                assert_eq!(3, b.len());
+            }
+            assert_eq!(vec![1, 2].into_iter().collect::<HashSet<_>>(), a);
+        }
+
+        #[test]
+        fn test_set_inserted_existing() {
+            let mut a: HashSet<i32> = vec![1, 2].into_iter().collect();
+            {
+                let b = a.inserted(2);
+                assert_eq!(2, b.len());
+            }
+            // The value was already there, so it must not be removed
+            assert_eq!(vec![1, 2].into_iter().collect::<HashSet<_>>(), a);
+        }
+
+        #[test]
+        fn test_set_removed() {
+            let mut a: HashSet<i32> = vec![1, 2].into_iter().collect();
+            {
+                let mut b = a.removed(&1);
+                assert!(!b.contains(&1));
+                assert_eq!(1, b.removed(&5).len());
+                assert_eq!(2, b.inserted(1).len());
+            }
+            assert_eq!(vec![1, 2].into_iter().collect::<HashSet<_>>(), a);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub use std_collections::*;
+
+    #[test]
+    fn test_scoped_vec() {
+        let mut a = vec![1];
+        {
+            let mut b = a.pushed(2);
+            {
+                assert_eq!([1, 2, 3], *b.pushed(3));
+            }
+            assert_eq!([1, 2, -3], *b.pushed(-3));
+        }
+        assert_eq!([1, -2], *a.pushed(-2));
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_noop() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([1, 2, 3], *a.nooped());
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_pop_empty() {
+        let mut a = Vec::<i32>::new();
+        {
+            assert_eq!([0i32; 0], *a.popped());
+        }
+        assert_eq!([0i32; 0], *a);
+    }
+
+    #[test]
+    fn test_pop() {
+        let mut a = vec![1];
+        {
+            assert_eq!([0i32; 0], *a.popped());
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_pop_push() {
+        let mut a = vec![1];
+        {
+            assert_eq!([-1], *a.popped().pushed(-1));
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_assigned() {
+        let mut a = vec![0, 1, 2, 3];
+        {
+            assert_eq!([0, 1, 5, 3], *a.assigned(2, 5))
+        }
+        assert_eq!([0, 1, 2, 3], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assigned_panics_with_out_of_bounds_index() {
+        let _ = vec![1].assigned(2, 5);
+    }
+
+    #[test]
+    fn test_inserted() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.inserted(1, 5);
+            assert_eq!([1, 5, 2, 3], *b);
+            {
+                let mut c = b.inserted(0, 4);
+                assert_eq!([4, 1, 5, 2, 3], *c);
+                assert_eq!([4, 1, 5, 2, 3, 6], *c.inserted(5, 6));
+            }
+            assert_eq!([1, 5, 2, 3], *b);
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_popped_n() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            let b = a.popped_n(2);
+            assert_eq!([1, 2], *b);
+            assert_eq!([3, 4], *b.popped_values());
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_popped_n_more_than_len() {
+        let mut a = vec![1, 2];
+        {
+            let mut b = a.popped_n(5);
+            assert!(b.is_empty());
+            assert_eq!([1, 2], *b.popped_values());
+            assert_eq!([0i32; 0], *b.popped_n(1).popped_values());
+        }
+        assert_eq!([1, 2], *a);
+        assert_eq!([1, 2], *a.popped_n(0));
+    }
+
+    #[test]
+    fn test_prepended() {
+        let mut a = vec![2, 3];
+        {
+            assert_eq!([1, 2, 3], *a.prepended(1));
+        }
+        assert_eq!([2, 3], *a);
+    }
+
+    #[test]
+    fn test_prepended_nested() {
+        let mut a = vec![3];
+        {
+            let mut b = a.prepended(2);
+            {
+                let c = b.prepended(1);
+                assert_eq!([1, 2, 3], *c);
+            }
+            assert_eq!([2, 3], *b);
+        }
+        assert_eq!([3], *a);
+    }
+
+    #[test]
+    fn test_inserted_at_end() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([1, 2, 3, 4], *a.inserted(3, 4));
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_inserted_panics_with_out_of_bounds_index() {
+        let _ = vec![1].inserted(2, 5);
+    }
+
+    #[test]
+    fn test_removed() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([1, 3], *a.removed(1));
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_removed_nested_in_pushed() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.pushed(4);
+            {
+                let mut c = b.removed(0);
+                assert_eq!([2, 3, 4], *c);
+                assert_eq!([2, 4], *c.removed(1));
+            }
+            assert_eq!([1, 2, 3, 4], *b);
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_removed_panics_with_out_of_bounds_index() {
+        let _ = vec![1].removed(1);
+    }
+
+    #[test]
+    fn test_swapped() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            assert_eq!([4, 2, 3, 1], *a.swapped(0, 3));
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_swapped_same_index() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([1, 2, 3], *a.swapped(1, 1));
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_swapped_adjacent() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([1, 3, 2], *a.swapped(2, 1));
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_swapped_nested_in_assigned() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.assigned(0, 5);
+            assert_eq!([3, 2, 5], *b.swapped(0, 2));
+            assert_eq!([5, 2, 3], *b);
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_swapped_panics_with_out_of_bounds_index() {
+        let _ = vec![1, 2].swapped(0, 2);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            assert_eq!([1], *a.truncated(1));
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_truncated_longer_than_len() {
+        let mut a = vec![1, 2];
+        {
+            assert_eq!([1, 2], *a.truncated(2));
+            assert_eq!([1, 2], *a.truncated(5));
+        }
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_pushed_nested_in_truncated() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            let mut b = a.truncated(2);
+            assert_eq!([1, 2, 5], *b.pushed(5));
+            assert_eq!([1, 2], *b);
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_extended() {
+        let mut a = vec![1];
+        {
+            let mut b = a.extended(vec![2, 3, 4]);
+            assert_eq!([1, 2, 3, 4], *b);
+            assert_eq!([1, 2, 3, 4, 5, 6], *b.extended(5..7));
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_extended_empty() {
+        let mut a = vec![1];
+        {
+            assert_eq!([1], *a.extended(Vec::new()));
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_reversed() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([3, 2, 1], *a.reversed());
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_chunks_reversed() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        {
+            assert_eq!([2, 1, 4, 3, 5], *a.chunks_reversed(2));
+            assert_eq!([3, 2, 1, 5, 4], *a.chunks_reversed(3));
+            assert_eq!([5, 4, 3, 2, 1], *a.chunks_reversed(7));
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+    }
+
+    #[test]
+    fn test_chunks_reversed_nested() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        {
+            let mut b = a.chunks_reversed(2);
+            assert_eq!([1, 2, 3, 4, 5], *b.chunks_reversed(2));
+            assert_eq!([2, 1, 4, 3, 5], *b);
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_chunks_reversed_panics_with_zero_chunk() {
+        let _ = vec![1, 2].chunks_reversed(0);
+    }
+
+    #[test]
+    fn test_reversed_even_len() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            assert_eq!([4, 3, 2, 1], *a.reversed());
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_reversed_empty() {
+        let mut a = Vec::<i32>::new();
+        {
+            assert_eq!([0i32; 0], *a.reversed());
+        }
+        assert_eq!([0i32; 0], *a);
+    }
+
+    #[test]
+    fn test_reversed_reversed() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([1, 2, 3], *a.reversed().reversed());
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_swap_removed() {
+        let mut a = vec![10, 20, 30, 40];
+        {
+            assert_eq!([10, 40, 30], *a.swap_removed(1));
+        }
+        assert_eq!([10, 20, 30, 40], *a);
+    }
+
+    #[test]
+    fn test_swap_removed_last() {
+        let mut a = vec![10, 20, 30];
+        {
+            assert_eq!([10, 20], *a.swap_removed(2));
+        }
+        assert_eq!([10, 20, 30], *a);
+    }
+
+    #[test]
+    fn test_swap_removed_nested() {
+        let mut a = vec![10, 20, 30, 40];
+        {
+            let mut b = a.swap_removed(0);
+            assert_eq!([40, 20, 30], *b);
+            assert_eq!([30, 20], *b.swap_removed(0));
+            assert_eq!([40, 20, 30], *b);
+        }
+        assert_eq!([10, 20, 30, 40], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_swap_removed_panics_with_out_of_bounds_index() {
+        let _ = vec![1].swap_removed(1);
+    }
+
+    #[test]
+    fn test_rotated_left() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        {
+            assert_eq!([3, 4, 5, 1, 2], *a.rotated_left(2));
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+    }
+
+    #[test]
+    fn test_rotated_left_edges() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        {
+            assert_eq!([1, 2, 3, 4, 5], *a.rotated_left(0));
+            assert_eq!([1, 2, 3, 4, 5], *a.rotated_left(5));
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rotated_left_panics_with_out_of_bounds_mid() {
+        let _ = vec![1, 2].rotated_left(3);
+    }
+
+    #[test]
+    fn test_rotated_right() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        {
+            assert_eq!([4, 5, 1, 2, 3], *a.rotated_right(2));
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+    }
+
+    #[test]
+    fn test_rotated_right_edges() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        {
+            assert_eq!([1, 2, 3, 4, 5], *a.rotated_right(0));
+            assert_eq!([1, 2, 3, 4, 5], *a.rotated_right(5));
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+    }
+
+    #[test]
+    fn test_rotated_right_nested_in_rotated_left() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        {
+            let mut b = a.rotated_left(1);
+            assert_eq!([1, 2, 3, 4, 5], *b.rotated_right(1));
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rotated_right_panics_with_out_of_bounds_k() {
+        let _ = vec![1, 2].rotated_right(3);
+    }
+
+    #[test]
+    fn test_retained() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        {
+            assert_eq!([2, 4, 6], *a.retained(|x| x % 2 == 0));
+        }
+        assert_eq!([1, 2, 3, 4, 5, 6], *a);
+    }
+
+    #[test]
+    fn test_retained_nothing() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([0i32; 0], *a.retained(|_| false));
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_retained_nested() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        {
+            let mut b = a.retained(|&x| x != 1 && x != 4);
+            assert_eq!([2, 3, 5, 6], *b);
+            assert_eq!([3, 5], *b.retained(|x| x % 2 == 1));
+            assert_eq!([2, 3, 5, 6], *b);
+        }
+        assert_eq!([1, 2, 3, 4, 5, 6], *a);
+    }
+
+    #[test]
+    fn test_with_pushed() {
+        let mut a = vec![1, 2, 3];
+        with_pushed(&mut a, 4, |b| {
+            assert_eq!([1, 2, 3, 4], **b);
+            with_pushed(b, 5, |c| assert_eq!([1, 2, 3, 4, 5], **c));
+            assert_eq!([1, 2, 3, 4], **b);
+        });
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_with_ops_return_value() {
+        let mut a = vec![1, 2, 3];
+        let sum: i32 = with_popped(&mut a, |b| {
+            with_assigned(b, 0, 10, |c| with_nooped(c, |d| d.iter().sum()))
+        });
+        assert_eq!(12, sum);
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_scoped_macro() {
+        let user_fn = |a: &mut Noop<Vec<i32>>| {
+            crate::scoped! { a => pushed(4), assigned(1, -2);
+                assert_eq!([1, -2, 3, 4], *a);
+            }
+        };
+
+        let mut a = vec![1, 2, 3];
+        user_fn(&mut a.nooped());
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_scoped_macro_returns_body() {
+        let mut a = vec![1, 2, 3];
+        let len = crate::scoped! { a => popped(), pushed(5), pushed(6), swapped(0, 1);
+            assert_eq!([2, 1, 5, 6], *a);
+            a.len()
+        };
+        assert_eq!(4, len);
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_scoped_macro_no_ops() {
+        let a = vec![1, 2, 3];
+        crate::scoped! { a => ; assert_eq!([1, 2, 3], *a) }
+    }
+
+    #[test]
+    fn test_commit_pushed() {
+        let mut a = vec![1, 2, 3];
+        a.pushed(4).commit();
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_commit_popped() {
+        let mut a = vec![1, 2, 3];
+        a.popped().commit();
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_commit_assigned() {
+        let mut a = vec![1, 2, 3];
+        a.assigned(0, 5).commit();
+        assert_eq!([5, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_commit_then_scoped() {
+        let mut a = vec![1, 2, 3];
+        a.pushed(4).commit();
+        {
+            let mut b = a.assigned(0, 5);
+            let c = b.popped();
+            assert_eq!([5, 2, 3], *c);
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_commit_other_ops() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        a.inserted(0, 0).commit();
+        a.removed(1).commit();
+        a.swap_removed(0).commit();
+        a.retained(|&x| x != 3).commit();
+        a.reversed().commit();
+        a.rotated_left(1).commit();
+        a.swapped(0, 1).commit();
+        a.nooped().commit();
+        assert_eq!([5, 2, 4], *a);
+    }
+
+    #[test]
+    fn test_try_assigned() {
+        let mut a = vec![1, 2, 3];
+        {
+            let b = a.try_assigned(1, 5).unwrap();
+            assert_eq!([1, 5, 3], *b);
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_try_assigned_out_of_bounds() {
+        let mut a = vec![String::from("a")];
+        let err = a.try_assigned(1, String::from("b")).err().unwrap();
+        assert_eq!(1, err.idx);
+        assert_eq!("b", err.value);
+        assert_eq!(
+            "assigned index (is 1) should be < len (is 1)",
+            err.to_string()
+        );
+        assert_eq!(["a"], *a);
+    }
+
+    #[test]
+    fn test_try_inserted() {
+        let mut a = vec![1, 2];
+        {
+            let b = a.try_inserted(2, 3).unwrap();
+            assert_eq!([1, 2, 3], *b);
+        }
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_try_inserted_out_of_bounds() {
+        let mut a = vec![String::from("a")];
+        let err = a.try_inserted(2, String::from("b")).err().unwrap();
+        assert_eq!(2, err.idx);
+        assert_eq!(1, err.len);
+        assert_eq!("b", err.value);
+        assert_eq!(
+            "insertion index (is 2) should be <= len (is 1)",
+            err.to_string()
+        );
+        assert_eq!(["a"], *a);
+    }
+
+    #[test]
+    fn test_deref_mut_pushed() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.pushed(4);
+            b[0] = 10;
+            b[3] = 40;
+            assert_eq!([10, 2, 3, 40], *b);
+        }
+        // The edit to an existing element persists, but the pushed element is still popped
+        assert_eq!([10, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_deref_mut_pushed_reordered() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.pushed(4);
+            b.swap(0, 3);
+            assert_eq!([4, 2, 3, 1], *b);
+        }
+        // Reverting pops by position, so the original 1 is lost and the pushed 4 stays
+        assert_eq!([4, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_deref_mut_popped() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.popped();
+            b.iter_mut().for_each(|x| *x *= 2);
+            assert_eq!([2, 4], *b);
+        }
+        assert_eq!([2, 4, 3], *a);
+    }
+
+    #[test]
+    fn test_deref_mut_assigned() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.assigned(1, 5);
+            b[0] = 7;
+            b[1] = 6;
+            assert_eq!([7, 6, 3], *b);
+        }
+        // The assigned index is restored regardless of edits made to it in the meantime
+        assert_eq!([7, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_deref_mut_nooped() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.nooped();
+            b.swap(0, 2);
+            let mut c = b.pushed(4);
+            assert_eq!([3, 2, 1, 4], *c);
+            c.reverse();
+            assert_eq!([4, 1, 2, 3], *c);
+        }
+        assert_eq!([4, 1, 2], *a);
+    }
+
+    #[test]
+    fn test_previous() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.assigned(1, 99);
+            assert_eq!(&2, b.previous());
+            assert_eq!(&99, b.assigned(1, 100).previous());
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_popped_value() {
+        let mut a = vec![1, 2];
+        {
+            let mut b = a.popped();
+            assert_eq!(Some(&2), b.popped_value());
+            let mut c = b.popped();
+            assert_eq!(Some(&1), c.popped_value());
+            assert_eq!(None, c.popped().popped_value());
+        }
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_pushed_value() {
+        let mut a = vec![1];
+        {
+            let mut b = a.pushed(2);
+            assert_eq!(&2, b.pushed_value());
+            assert_eq!(&3, b.pushed(3).pushed_value());
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_deque_pushed_front() {
+        let mut a: VecDeque<i32> = vec![2, 3].into();
+        {
+            let mut b = a.pushed_front(1);
+            assert_eq!(VecDeque::from(vec![1, 2, 3]), *b);
+            let c = b.pushed_back(4);
+            assert_eq!(Some(&1), c.front());
+            assert_eq!(Some(&4), c.back());
+            assert_eq!(4, c.len());
+        }
+        assert_eq!(VecDeque::from(vec![2, 3]), a);
+    }
+
+    #[test]
+    fn test_deque_popped() {
+        let mut a: VecDeque<i32> = vec![1, 2, 3].into();
+        {
+            let mut b = a.popped_front();
+            assert_eq!(VecDeque::from(vec![2, 3]), *b);
+            assert_eq!(VecDeque::from(vec![2]), *b.popped_back());
+            assert_eq!(VecDeque::from(vec![0, 2, 3]), *b.pushed_front(0));
+        }
+        assert_eq!(VecDeque::from(vec![1, 2, 3]), a);
+    }
+
+    #[test]
+    fn test_deque_popped_empty() {
+        let mut a = VecDeque::<i32>::new();
+        {
+            assert!(a.popped_front().is_empty());
+            assert!(a.popped_back().is_empty());
+        }
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_deque_commit() {
+        let mut a: VecDeque<i32> = vec![1, 2].into();
+        a.pushed_front(0).commit();
+        a.popped_back().commit();
+        assert_eq!(VecDeque::from(vec![0, 1]), a);
+    }
+
+    #[test]
+    fn test_slice_assigned_swapped() {
+        let mut array = [1, 2, 3];
+        {
+            let mut a = &mut array[..];
+            {
+                let mut b = a.assigned(0, 4);
+                assert_eq!([4, 2, 3], *b);
+                assert_eq!([3, 2, 4], *b.swapped(0, 2));
+            }
+            assert_eq!([1, 2, 3], *a);
+        }
+        assert_eq!([1, 2, 3], array);
+    }
+
+    #[test]
+    fn test_slice_reversed_sorted() {
+        let mut array = [3, 1, 2];
+        {
+            let mut a = &mut array[..];
+            let mut b = a.reversed();
+            assert_eq!([2, 1, 3], *b);
+            assert_eq!([1, 2, 3], *b.sorted());
+            assert_eq!("SliceReverse([2, 1, 3])", format!("{:?}", b));
+        }
+        assert_eq!([3, 1, 2], array);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_slice_assigned_panics_with_out_of_bounds_index() {
+        let mut array = [1];
+        let _ = (&mut array[..]).assigned(1, 2);
+    }
+
+    #[test]
+    fn test_string_pushed_str() {
+        let mut a = String::from("hello");
+        {
+            let mut b = a.pushed_str(" world");
+            assert_eq!("hello world", &*b);
+            assert_eq!("hello world!", &*b.pushed_char('!'));
+        }
+        assert_eq!("hello", a);
+    }
+
+    #[test]
+    fn test_string_multi_byte() {
+        let mut a = String::from("naïve");
+        {
+            let mut b = a.pushed_char('é');
+            assert_eq!("naïveé", &*b);
+            assert_eq!("naïveé 🦀 ✓", &*b.pushed_str(" 🦀 ✓"));
+        }
+        assert_eq!("naïve", a);
+    }
+
+    #[test]
+    fn test_btree_map_inserted() {
+        let mut a: BTreeMap<i32, &str> = vec![(1, "a"), (3, "c")].into_iter().collect();
+        let original = a.clone();
+        {
+            let mut b = a.inserted(2, "b");
+            assert_eq!(
+                vec![(&1, &"a"), (&2, &"b"), (&3, &"c")],
+                b.iter().collect::<Vec<_>>()
+            );
+            let c = b.inserted(3, "z");
+            assert_eq!(
+                vec![(&1, &"a"), (&2, &"b"), (&3, &"z")],
+                c.iter().collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(original, a);
+        assert_eq!(vec![(&1, &"a"), (&3, &"c")], a.iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_btree_map_removed() {
+        let mut a: BTreeMap<i32, &str> = vec![(1, "a"), (2, "b"), (3, "c")].into_iter().collect();
+        let original = a.clone();
+        {
+            let b = a.removed(&2);
+            assert_eq!(vec![&1, &3], b.keys().collect::<Vec<_>>());
+        }
+        assert_eq!(original, a);
+    }
+
+    #[test]
+    fn test_heap_pushed() {
+        let mut a: BinaryHeap<i32> = vec![3, 1, 4, 1, 5].into();
+        let original = a.clone().into_vec();
+        {
+            let mut b = a.pushed(9);
+            assert_eq!(Some(&9), b.peek());
+            assert_eq!(6, b.len());
+            let c = b.pushed(2);
+            assert_eq!(Some(&9), c.peek());
+            assert_eq!(7, c.len());
+        }
+        assert_eq!(Some(&5), a.peek());
+        assert_eq!(original, a.clone().into_vec());
+        assert_eq!(vec![1, 1, 3, 4, 5], a.into_sorted_vec());
+    }
+
+    #[test]
+    fn test_heap_pushed_commit() {
+        let mut a: BinaryHeap<i32> = vec![1, 2].into();
+        a.pushed(3).commit();
+        assert_eq!(vec![1, 2, 3], a.into_sorted_vec());
+    }
+
+    // Without `std`, the crate is `no_std` even in tests, so this checks that the collections from
+    // `alloc` still work there
+    #[test]
+    #[cfg(not(feature = "std"))]
+    fn test_no_std() {
+        let mut a = vec![1, 2, 3];
+        let mut b: VecDeque<i32> = a.iter().copied().collect();
+        let mut c = String::from("abc");
+        let mut d: BTreeMap<i32, i32> = BTreeMap::new();
+        {
+            assert_eq!([1, 2, 4], *a.popped().pushed(4));
+            assert_eq!(Some(&0), b.pushed_front(0).front());
+            assert_eq!("abcd", &*c.pushed_char('d'));
+            assert_eq!(Some(&2), d.inserted(1, 2).get(&1));
+        }
+        assert_eq!([1, 2, 3], *a);
+        assert_eq!(3, b.len());
+        assert_eq!("abc", c);
+        assert!(d.is_empty());
+    }
+
+    #[test]
+    fn test_debug() {
+        let mut a = vec![1, 2, 3];
+        assert_eq!(
+            "Assign { vec: [1, 4, 3], idx: 1, previous: 2 }",
+            format!("{:?}", a.assigned(1, 4))
+        );
+        assert_eq!("Extend([1, 2, 3, 4])", format!("{:?}", a.extended(vec![4])));
+        assert_eq!("Insert([0, 1, 2, 3])", format!("{:?}", a.inserted(0, 0)));
+        assert_eq!("Noop([1, 2, 3])", format!("{:?}", a.nooped()));
+        assert_eq!(
+            "Pop { vec: [1, 2], popped: Some(3) }",
+            format!("{:?}", a.popped())
+        );
+        assert_eq!("Push([1, 2, 3, 4])", format!("{:?}", a.pushed(4)));
+        assert_eq!("Remove([2, 3])", format!("{:?}", a.removed(0)));
+        assert_eq!("Retain([1, 3])", format!("{:?}", a.retained(|&x| x != 2)));
+        assert_eq!("Reverse([3, 2, 1])", format!("{:?}", a.reversed()));
+        assert_eq!("RotateLeft([2, 3, 1])", format!("{:?}", a.rotated_left(1)));
+        assert_eq!("SwapRemove([3, 2])", format!("{:?}", a.swap_removed(0)));
+        assert_eq!("Swap([3, 2, 1])", format!("{:?}", a.swapped(0, 2)));
+        assert_eq!("Truncate([1])", format!("{:?}", a.truncated(1)));
+        assert_eq!(
+            "Push([1, 2, 3, 4, 5])",
+            format!("{:?}", a.pushed(4).pushed(5))
+        );
+    }
+
+    #[test]
+    fn test_as_ref() {
+        fn sum(v: impl AsRef<[i32]>) -> i32 {
+            v.as_ref().iter().sum()
+        }
+        fn borrowed_sum<B: core::borrow::Borrow<[i32]>>(v: &B) -> i32 {
+            v.borrow().iter().sum()
+        }
+
+        let mut a = vec![1, 2, 3];
+        assert_eq!(10, sum(a.pushed(4)));
+        assert_eq!(3, sum(a.popped()));
+        assert_eq!(8, sum(a.assigned(0, 3)));
+        assert_eq!(10, sum(a.extended(vec![4])));
+        assert_eq!(6, sum(a.inserted(0, 0)));
+        assert_eq!(6, sum(a.nooped()));
+        assert_eq!(5, sum(a.removed(0)));
+        assert_eq!(6, sum(a.reversed()));
+        assert_eq!(6, sum(a.sorted()));
+        assert_eq!(1, sum(a.truncated(1)));
+        assert_eq!(15, sum(a.pushed(4).pushed(5)));
+        assert_eq!(10, borrowed_sum(&a.pushed(4)));
+        assert_eq!(3, borrowed_sum(&a.popped()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let mut a = vec![1, 2, 3];
+        assert_eq!("[1,2,3,4]", serde_json::to_string(&a.pushed(4)).unwrap());
+        assert_eq!("[1,5,3]", serde_json::to_string(&a.assigned(1, 5)).unwrap());
+        assert_eq!("[1,2]", serde_json::to_string(&a.popped()).unwrap());
+        assert_eq!("[1,2,3]", serde_json::to_string(&a.nooped()).unwrap());
+    }
+
+    #[test]
+    fn test_display() {
+        let mut a = vec![1, 2, 3];
+        assert_eq!("[1, 2, 3, 4]", a.pushed(4).to_string());
+        assert_eq!("[1, 4, 3]", a.assigned(1, 4).to_string());
+        assert_eq!("[1, 2]", a.popped().to_string());
+        assert_eq!("[]", a.truncated(0).to_string());
+        assert_eq!("[1, 2, 3, 4, 5]", a.pushed(4).pushed(5).to_string());
+        assert_eq!("[1.0, 2.0]", format!("{:.1}", vec![1.0, 2.0].nooped()));
+        assert_eq!("[a, b]", vec!["a"].pushed("b").to_string());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash_of<H: Hash + ?Sized>(value: &H) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            value.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut a = vec![1, 2, 3];
+        let pushed = hash_of(&a.pushed(4));
+        assert_eq!(hash_of(&[1, 2, 3, 4][..]), pushed);
+        assert_eq!(hash_of(&vec![1, 2, 3, 4]), pushed);
+        assert_eq!(hash_of(&[1, 5, 3][..]), hash_of(&a.assigned(1, 5)));
+        let nooped = hash_of(&a.nooped());
+        assert_eq!(hash_of(&a), nooped);
+    }
+
+    #[test]
+    fn test_debug_other_collections() {
+        let mut d = VecDeque::from(vec![1, 2]);
+        assert_eq!("PushFront([0, 1, 2])", format!("{:?}", d.pushed_front(0)));
+        let mut s = "ab".to_string();
+        assert_eq!("PushStr(\"abcd\")", format!("{:?}", s.pushed_str("cd")));
+        let mut m = BTreeMap::new();
+        m.insert(1, 'a');
+        assert_eq!("BTreeMapRemove({})", format!("{:?}", m.removed(&1)));
+    }
+
+    #[test]
+    fn test_eq_slice() {
+        let mut a = vec![1, 2, 3];
+        assert_eq!(a.assigned(0, 4), [4, 2, 3]);
+        assert_eq!(a.extended(vec![4]), [1, 2, 3, 4][..]);
+        assert_eq!(a.inserted(0, 0), &[0, 1, 2, 3][..]);
+        assert_eq!(a.nooped(), vec![1, 2, 3]);
+        assert_eq!(a.popped(), [1, 2]);
+        assert_eq!(a.pushed(4), [1, 2, 3, 4][..]);
+        assert_eq!(a.removed(0), &[2, 3][..]);
+        assert_eq!(a.retained(|&x| x != 2), vec![1, 3]);
+        assert_eq!(a.reversed(), [3, 2, 1]);
+        assert_eq!(a.rotated_left(1), [2, 3, 1][..]);
+        assert_eq!(a.swap_removed(0), &[3, 2][..]);
+        assert_eq!(a.swapped(0, 2), vec![3, 2, 1]);
+        assert_eq!(a.truncated(1), [1]);
+        assert_ne!(a.pushed(4), [1, 2, 3]);
+        assert_eq!(a, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        fn collect<'b>(v: impl IntoIterator<Item = &'b i32>) -> Vec<i32> {
+            v.into_iter().copied().collect()
+        }
+
+        let mut a = vec![1, 2, 3];
+        assert_eq!(vec![4, 2, 3], collect(&a.assigned(0, 4)));
+        assert_eq!(vec![1, 2, 3, 4], collect(&a.extended(vec![4])));
+        assert_eq!(vec![0, 1, 2, 3], collect(&a.inserted(0, 0)));
+        assert_eq!(vec![1, 2, 3], collect(&a.nooped()));
+        assert_eq!(vec![1, 2], collect(&a.popped()));
+        assert_eq!(vec![2, 3], collect(&a.removed(0)));
+        assert_eq!(vec![1, 3], collect(&a.retained(|&x| x != 2)));
+        assert_eq!(vec![3, 2, 1], collect(&a.reversed()));
+        assert_eq!(vec![2, 3, 1], collect(&a.rotated_left(1)));
+        assert_eq!(vec![3, 2], collect(&a.swap_removed(0)));
+        assert_eq!(vec![3, 2, 1], collect(&a.swapped(0, 2)));
+        assert_eq!(vec![1], collect(&a.truncated(1)));
+
+        let mut sum = 0;
+        for x in &a.pushed(4) {
+            sum += x;
+        }
+        assert_eq!(10, sum);
+    }
+
+    #[test]
+    fn test_index() {
+        let mut a = vec![1, 2, 3];
+        assert_eq!(4, a.assigned(0, 4)[0]);
+        assert_eq!([3, 4], a.extended(vec![4])[2..]);
+        assert_eq!([0, 1], a.inserted(0, 0)[..2]);
+        assert_eq!(2, a.nooped()[1]);
+        assert_eq!([1, 2], a.popped()[0..2]);
+        assert_eq!(4, a.pushed(4)[3]);
+        assert_eq!(3, a.removed(0)[1]);
+        assert_eq!([1, 3], a.retained(|&x| x != 2)[..]);
+        assert_eq!([3, 2], a.reversed()[0..=1]);
+        assert_eq!(1, a.rotated_left(1)[2]);
+        assert_eq!(3, a.swap_removed(0)[0]);
+        assert_eq!([2, 1], a.swapped(0, 2)[1..]);
+        assert_eq!(1, a.truncated(1)[0]);
+
+        let mut b = a.pushed(4);
+        let c = b.assigned(3, 5);
+        assert_eq!(5, c[3]);
+        assert_eq!([2, 3, 5], c[1..]);
+    }
+
+    #[test]
+    fn test_len() {
+        let mut a = vec![1];
+        let mut b = a.pushed(2);
+        assert_eq!(2, b.len());
+        assert!(!b.is_empty());
+        {
+            let mut c = b.popped();
+            let d = c.popped();
+            assert_eq!(0, d.len());
+            assert!(d.is_empty());
+        }
+        assert_eq!(2, b.len());
+    }
+
+    #[test]
+    fn test_read_only_helpers() {
+        let mut a = vec![1, 2];
+        let mut b = a.pushed(3);
+        assert_eq!(Some(&1), b.first());
+        assert_eq!(Some(&3), b.last());
+        assert!(b.contains(&3));
+        let c = b.popped();
+        assert_eq!(Some(&2), c.last());
+        assert!(!c.contains(&3));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut a = vec![1, 2];
+        let snapshot = {
+            let b = a.pushed(3);
+            b.snapshot()
+        };
+        assert_eq!(vec![1, 2, 3], snapshot);
+        assert_eq!([1, 2], *a);
+        assert_eq!(vec![1, 2], a.snapshot());
+    }
+
+    #[test]
+    fn test_split_view() {
+        let mut a = vec![1, 2, 3];
+        let b = a.pushed(4);
+        assert_eq!((&[1, 2][..], &[3, 4][..]), b.split_view(2));
+        assert_eq!((&[][..], &[1, 2, 3, 4][..]), b.split_view(0));
+        assert_eq!((&[1, 2, 3, 4][..], &[][..]), b.split_view(4));
+    }
+
+    #[test]
+    fn test_iter_rev() {
+        let mut a = vec![1, 2];
+        assert_eq!(Some(&9), a.pushed(9).iter_rev().next());
+        let b = a.pushed(3);
+        assert_eq!(vec![&3, &2, &1], b.iter_rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_windows() {
+        let mut a = vec![1, 2, 3];
+        {
+            let b = a.pushed(4);
+            let windows: Vec<_> = b.windows(2).collect();
+            assert_eq!(vec![&[1, 2], &[2, 3], &[3, 4]], windows);
+        }
+        assert_eq!(2, a.windows(2).count());
+    }
+
+    #[test]
+    fn test_chunks() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.pushed(4);
+            assert_eq!(
+                vec![&[1, 2][..], &[3, 4][..]],
+                b.chunks(2).collect::<Vec<_>>()
+            );
+            let c = b.pushed(5);
+            let chunks: Vec<_> = c.chunks(2).collect();
+            assert_eq!(vec![&[1, 2][..], &[3, 4][..], &[5][..]], chunks);
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_resized_longer() {
+        let mut a = vec![1, 2];
+        {
+            assert_eq!([1, 2, 0, 0], *a.resized(4, 0));
+        }
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_resized_shorter() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            let mut b = a.resized(2, 0);
+            assert_eq!([1, 2], *b);
+            assert_eq!([1, 2, 5, 5, 5], *b.resized(5, 5));
+            assert_eq!([1, 2], *b);
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_with_resized() {
+        let mut a = vec![1, 2];
+        assert_eq!(3, with_resized(&mut a, 3, 7, |b| b.len()));
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_appended() {
+        let mut a = vec![1, 2, 3];
+        let mut other = vec![4, 5];
+        {
+            let mut b = a.appended(&mut other);
+            assert_eq!([1, 2, 3, 4, 5], *b);
+            assert_eq!([1, 2, 3, 4, 5, 6], *b.pushed(6));
+        }
+        assert_eq!([1, 2, 3], *a);
+        assert_eq!([4, 5], *other);
+    }
+
+    #[test]
+    fn test_appended_commit() {
+        let mut a = vec![1];
+        let mut other = vec![2];
+        a.appended(&mut other).commit();
+        assert_eq!([1, 2], *a);
+        assert!(other.is_empty());
+    }
+
+    #[test]
+    fn test_split_off_at() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            let mut b = a.split_off_at(2);
+            assert_eq!([1, 2], *b);
+            assert_eq!([3, 4], *b.tail());
+            assert_eq!([1, 2, 5], *b.pushed(5));
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_split_off_at_len() {
+        let mut a = vec![1, 2];
+        assert!(a.split_off_at(2).tail().is_empty());
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_split_off_at_panics_with_out_of_bounds_index() {
+        let _ = vec![1, 2].split_off_at(3);
+    }
+
+    #[test]
+    fn test_deduped() {
+        let mut a = vec![1, 1, 2, 3, 3, 3];
+        {
+            let mut b = a.deduped();
+            assert_eq!([1, 2, 3], *b);
+            assert_eq!([1, 2, 3, 4], *b.pushed(4));
+        }
+        assert_eq!([1, 1, 2, 3, 3, 3], *a);
+    }
+
+    #[test]
+    fn test_deduped_no_duplicates() {
+        let mut a = vec![1, 2, 1];
+        {
+            assert_eq!([1, 2, 1], *a.deduped());
+        }
+        assert_eq!([1, 2, 1], *a);
+    }
+
+    #[test]
+    fn test_deduped_nested_in_pushed() {
+        let mut a = vec![2, 2, 1];
+        {
+            let mut b = a.pushed(1);
+            assert_eq!([2, 1], *b.deduped());
+            assert_eq!([2, 2, 1, 1], *b);
+        }
+        assert_eq!([2, 2, 1], *a);
+    }
+
+    #[test]
+    fn test_sorted() {
+        let mut a = vec![3, 1, 2];
+        {
+            let mut b = a.sorted();
+            assert_eq!([1, 2, 3], *b);
+            assert_eq!([3, 2, 1], *b.reversed());
+        }
+        assert_eq!([3, 1, 2], *a);
+    }
+
+    #[test]
+    fn test_sorted_deduped() {
+        let mut a = vec![3, 1, 2, 1, 3];
+        {
+            let mut b = a.sorted_deduped();
+            assert_eq!([1, 2, 3], *b);
+            assert_eq!([1, 2, 3, 1], *b.pushed(1));
+        }
+        assert_eq!([3, 1, 2, 1, 3], *a);
+    }
+
+    #[test]
+    fn test_sorted_tracked() {
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+        struct NotClone(i32);
+
+        let mut a = vec![
+            NotClone(3),
+            NotClone(1),
+            NotClone(4),
+            NotClone(2),
+            NotClone(0),
+        ];
+        {
+            let mut b = a.sorted_tracked();
+            assert_eq!(
+                [
+                    NotClone(0),
+                    NotClone(1),
+                    NotClone(2),
+                    NotClone(3),
+                    NotClone(4)
+                ],
+                *b
+            );
+            assert_eq!(NotClone(4), *b.popped().popped_value().unwrap());
+        }
+        assert_eq!(
+            [
+                NotClone(3),
+                NotClone(1),
+                NotClone(4),
+                NotClone(2),
+                NotClone(0)
+            ],
+            *a
+        );
+    }
+
+    #[test]
+    fn test_sorted_tracked_with_duplicates() {
+        let mut a = vec![(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')];
+        {
+            let mut b = a.sorted_tracked();
+            assert_eq!([(1, 'b'), (1, 'd'), (2, 'a'), (2, 'c')], *b);
+            assert_eq!([(2, 'c'), (2, 'a'), (1, 'd'), (1, 'b')], *b.reversed());
+        }
+        assert_eq!([(2, 'a'), (1, 'b'), (2, 'c'), (1, 'd')], *a);
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let mut a = vec![1, 2, 3];
+        let b = a.pushed(4);
+        assert_eq!([1, 2, 3, 4], *b);
+        let a = b.into_inner();
+        assert_eq!([1, 2, 3], **a);
+        let mut c = a.truncated(1);
+        let d = c.assigned(0, 5);
+        assert_eq!([5], *d);
+        let c = d.into_inner();
+        assert_eq!([1], **c);
+        assert_eq!([1, 6], *c.pushed(6));
+        assert_eq!([1], **c);
+    }
+
+    #[test]
+    fn test_into_inner_reverts_once() {
+        let mut a = vec![1, 2, 3];
+        assert_eq!([1, 2, 3], **a.popped().into_inner());
+        assert_eq!([1, 2, 3], **a.sorted_tracked().into_inner());
+        assert_eq!([1, 2, 3], **a.split_off_at(1).into_inner());
+        let mut d = VecDeque::from(vec![1, 2]);
+        assert_eq!(VecDeque::from(vec![1, 2]), *d.popped_front().into_inner());
+        let mut s = "ab".to_string();
+        assert_eq!("ab", s.pushed_str("cd").into_inner());
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_extended_from_slice() {
+        let mut a = vec![1];
+        {
+            let mut b = a.extended_from_slice(&[2, 3, 4]);
+            assert_eq!([1, 2, 3, 4], *b);
+            assert_eq!([1, 2, 3], *b.popped());
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_extended_from_empty_slice() {
+        let mut a = vec![1];
+        {
+            assert_eq!([1], *a.extended_from_slice(&[]));
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_filled() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.filled(9);
+            assert_eq!([9, 9, 9], *b);
+            assert_eq!([9, 1, 9], *b.assigned(1, 1));
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_filled_empty() {
+        let mut a: Vec<i32> = vec![];
+        {
+            assert!(a.filled(9).is_empty());
+        }
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_drained() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            let mut b = a.drained(1..3);
+            assert_eq!([1, 4], *b);
+            assert_eq!([1, 4, 5], *b.pushed(5));
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_drained_full_range() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert!(a.drained(..).is_empty());
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_drained_empty_range() {
+        let mut a = vec![1, 2, 3];
+        {
+            assert_eq!([1, 2, 3], *a.drained(2..2));
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_drained_panics_with_out_of_bounds_range() {
+        let _ = vec![1, 2].drained(1..3);
+    }
+
+    #[test]
+    fn test_depth() {
+        let mut a = vec![1, 2];
+        assert_eq!(0, a.depth());
+        assert_eq!(1, a.nooped().depth());
+        let mut b = a.pushed(1);
+        let mut c = b.pushed(2);
+        let d = c.assigned(0, 5);
+        assert_eq!(3, d.depth());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_reverted_after_panic() {
+        let mut a = vec![1, 2, 3];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let mut b = a.pushed(4);
+            let _c = b.assigned(0, 5);
+            panic!("panicking while scoped");
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_retained_reverted_after_panic() {
+        let mut a = vec![1, 2, 3, 4];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _b = a.retained(|&x| if x == 3 { panic!("oh no") } else { x % 2 == 0 });
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_retained_in_reverted_after_panic() {
+        let mut a = vec![1, 2, 3, 4];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _b = a.retained_in(0..4, |&x| if x == 3 { panic!("oh no") } else { x % 2 == 0 });
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_retained_mut_reverted_after_panic() {
+        let mut a = vec![1, 2, 3, 4];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _b = a.retained_mut(|x| {
+                if *x == 3 {
+                    panic!("oh no")
+                } else {
+                    *x % 2 == 0
+                }
+            });
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_mapped_reverted_after_panic() {
+        let mut a = vec![1, 2, 3];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _b = a.mapped(|&x| if x == 3 { panic!("oh no") } else { x * 10 });
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_mapped_indexed_reverted_after_panic() {
+        let mut a = vec![1, 2, 3, 4];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _b = a.mapped_indexed(|idx, &x| if idx == 2 { panic!("oh no") } else { x * 10 });
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_spliced_reverted_after_panic() {
+        let mut a = vec![1, 2, 3];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let replacement = (0..).map(|x| if x == 2 { panic!("oh no") } else { x });
+            let _b = a.spliced(0..1, replacement);
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_extended_reverted_after_panic() {
+        let mut a = vec![1, 2, 3];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _b = a.extended((4..).map(|x| if x == 6 { panic!("oh no") } else { x }));
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_mapped() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.mapped(|x| x * 2);
+            assert_eq!([2, 4, 6], *b);
+            assert_eq!([3, 5, 7], *b.mapped(|x| x + 1));
+            assert_eq!([2, 4, 6], *b);
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_pushed_with_capacity() {
+        let mut a = vec![1, 2, 3];
+        a.shrink_to_fit();
+        {
+            let b = a.pushed_with_capacity(4, 100);
+            assert_eq!([1, 2, 3, 4], *b);
+            assert!(b.vec_ref().capacity() >= 103);
+        }
+        assert_eq!([1, 2, 3], *a);
+        assert!(a.capacity() >= 103);
+
+        let capacity = a.capacity();
+        for i in 0..100 {
+            assert_eq!(Some(&i), a.pushed(i).last());
+        }
+        assert_eq!(capacity, a.capacity());
+    }
+
+    #[test]
+    fn test_capacity_not_shrunk() {
+        fn assert_capacity_kept(f: impl FnOnce(&mut Vec<i32>)) {
+            let mut a = Vec::with_capacity(16);
+            a.extend_from_slice(&[3, 1, 1, 2]);
+            let capacity = a.capacity();
+            f(&mut a);
+            assert_eq!([3, 1, 1, 2], *a);
+            assert!(a.capacity() >= capacity);
+        }
+
+        assert_capacity_kept(|a| drop(a.deduped()));
+        assert_capacity_kept(|a| drop(a.drained(1..3)));
+        assert_capacity_kept(|a| drop(a.filled(0)));
+        assert_capacity_kept(|a| drop(a.mapped(|x| x + 1)));
+        assert_capacity_kept(|a| drop(a.popped()));
+        assert_capacity_kept(|a| drop(a.resized(1, 0)));
+        assert_capacity_kept(|a| drop(a.retained(|&x| x == 1)));
+        assert_capacity_kept(|a| drop(a.sorted()));
+        assert_capacity_kept(|a| drop(a.sorted_tracked()));
+        assert_capacity_kept(|a| drop(a.split_off_at(1)));
+        assert_capacity_kept(|a| drop(a.truncated(0)));
+        assert_capacity_kept(|a| {
+            let mut b = a.truncated(0);
+            let _c = b.deduped();
+        });
+    }
+
+    #[test]
+    fn test_first_last_get() {
+        let mut a = vec![1, 2, 3];
+        assert_eq!(Some(&9), a.pushed(9).last());
+        assert_eq!(Some(&2), a.popped().last());
+        assert_eq!(Some(&5), a.assigned(0, 5).first());
+        assert_eq!(Some(&9), a.pushed(9).get(3));
+        assert_eq!(None, a.popped().get(2));
+        let mut b = vec![1];
+        assert_eq!(None, b.popped().first());
+    }
+
+    #[test]
+    fn test_contains() {
+        let mut a = vec![1, 2, 3];
+        assert!(a.pushed(7).contains(&7));
+        assert!(!a.popped().contains(&3));
+        assert!(a.assigned(0, 8).contains(&8));
+        assert!(!a.contains(&7));
+    }
+
+    #[test]
+    fn test_apply_all() {
+        let mut a = vec![1, 2, 3];
+        let mut ops = Vec::new();
+        for i in 0..3 {
+            ops.push(Op::Push(i));
+        }
+        ops.push(Op::Pop);
+        ops.push(Op::Assign(0, 5));
+        let mut seen = Vec::new();
+        apply_all(&mut a, &ops, |v| seen.extend_from_slice(v));
+        assert_eq!([5, 2, 3, 0, 1], *seen);
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_apply_all_pop_empty() {
+        let mut a = vec![1];
+        apply_all(&mut a, &[Op::Pop, Op::Pop, Op::Push(2)], |v| {
+            assert_eq!([2], *v)
+        });
+        assert_eq!([1], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_apply_all_reverted_after_panic() {
+        let mut a = vec![1, 2];
+        let ops = [Op::Push(3), Op::Assign(0, 4), Op::Assign(5, 6)];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            apply_all(&mut a, &ops, |_| {})
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_with_dyn_op() {
+        struct PushOp(i32);
+
+        impl ScopedOp<i32> for PushOp {
+            fn apply(&mut self, v: &mut Vec<i32>) {
+                v.push(self.0)
+            }
+
+            fn revert(&mut self, v: &mut Vec<i32>) {
+                v.pop();
+            }
+        }
+
+        struct DoubleFirst(Option<i32>);
+
+        impl ScopedOp<i32> for DoubleFirst {
+            fn apply(&mut self, v: &mut Vec<i32>) {
+                self.0 = Some(v[0]);
+                v[0] *= 2;
+            }
+
+            fn revert(&mut self, v: &mut Vec<i32>) {
+                v[0] = self.0.take().unwrap();
+            }
+        }
+
+        let mut a = vec![1, 2];
+        let mut ops: Vec<Box<dyn ScopedOp<i32>>> =
+            vec![Box::new(PushOp(3)), Box::new(DoubleFirst(None))];
+        let mut seen = Vec::new();
+        for op in &mut ops {
+            with_dyn_op(&mut a, op.as_mut(), |v| seen.push(v.to_vec()));
+            assert_eq!([1, 2], *a);
+        }
+        assert_eq!(vec![vec![1, 2, 3], vec![2, 2]], seen);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_with_dyn_op_reverted_after_panic() {
+        struct PushOp;
+
+        impl ScopedOp<i32> for PushOp {
+            fn apply(&mut self, v: &mut Vec<i32>) {
+                v.push(3)
+            }
+
+            fn revert(&mut self, v: &mut Vec<i32>) {
+                v.pop();
+            }
+        }
+
+        let mut a = vec![1, 2];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            with_dyn_op(&mut a, &mut PushOp, |_| panic!("oh no"))
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_dyn_scoped_vec_loop() {
+        let mut a = vec![1, 2];
+        {
+            let mut s = DynScopedVec::new(&mut a);
+            for i in 0..3 {
+                s.push(i);
+            }
+            assert_eq!([1, 2, 0, 1, 2], *s);
+            assert_eq!(Some(&2), s.pop());
+            s.assign(0, 5);
+            assert_eq!([5, 2, 0, 1], *s);
+        }
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_dyn_scoped_vec_pop_empty() {
+        let mut a = vec![1];
+        {
+            let mut s = DynScopedVec::new(&mut a);
+            assert_eq!(Some(&1), s.pop());
+            assert_eq!(None, s.pop());
+            s.push(2);
+            assert_eq!([2], *s);
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dyn_scoped_vec_assign_panics_with_out_of_bounds_index() {
+        let mut a = vec![1];
+        DynScopedVec::new(&mut a).assign(1, 2);
+    }
+
+    #[test]
+    fn test_dyn_scoped_vec_undo_last() {
+        let mut a = vec![1];
+        {
+            let mut s = DynScopedVec::new(&mut a);
+            s.push(2);
+            s.push(3);
+            s.push(4);
+            assert!(s.undo_last());
+            assert!(s.undo_last());
+            assert_eq!([1, 2], *s);
+            s.assign(0, 5);
+            assert!(s.undo_last());
+            assert_eq!([1, 2], *s);
+        }
+        assert_eq!([1], *a);
+        assert!(!DynScopedVec::new(&mut a).undo_last());
+    }
+
+    #[test]
+    fn test_dyn_scoped_vec_rollback_to() {
+        let mut a = vec![];
+        {
+            let mut s = DynScopedVec::new(&mut a);
+            s.push(1);
+            s.push(2);
+            let checkpoint = s.checkpoint();
+            s.push(3);
+            s.assign(0, 5);
+            s.push(4);
+            assert_eq!([5, 2, 3, 4], *s);
+            s.rollback_to(checkpoint);
+            assert_eq!([1, 2], *s);
+            s.rollback_to(checkpoint);
+            assert_eq!([1, 2], *s);
+        }
+        assert!(a.is_empty());
+    }
+
+    #[test]
+    fn test_scoped_builder() {
+        let mut a = vec![1, 2];
+        let decisions = vec![Some(3), None, Some(4), None, Some(5)];
+        {
+            let mut b = ScopedBuilder::new(&mut a);
+            for decision in decisions {
+                b.maybe_push(decision);
+            }
+            assert_eq!([1, 2, 3, 4, 5], *b);
+            assert_eq!(3, b.recorded());
+            assert_eq!(None, b.maybe_pop(false));
+            assert_eq!(Some(&5), b.maybe_pop(true));
+            assert!(!b.maybe_assign(0, None));
+            assert!(b.maybe_assign(0, Some(6)));
+            assert_eq!([6, 2, 3, 4], *b);
+            assert_eq!(5, b.recorded());
+        }
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_scoped_builder_pop_empty() {
+        let mut a = vec![1];
+        {
+            let mut b = ScopedBuilder::new(&mut a);
+            for pop in [true, true, false, true] {
+                b.maybe_pop(pop);
+            }
+            assert!(b.is_empty());
+            assert_eq!(1, b.recorded());
+        }
+        assert_eq!([1], *a);
+    }
+
+    #[test]
+    fn test_scoped_builder_commit() {
+        let mut a = vec![1];
+        let mut b = ScopedBuilder::new(&mut a);
+        b.maybe_push(Some(2));
+        b.maybe_push(None);
+        b.commit();
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_frozen() {
+        let mut a = vec![1, 2];
+        {
+            let mut b = a.pushed(3);
+            let c = b.assigned(0, 4).frozen();
+            assert_eq!([4, 2, 3], *c);
+            assert_eq!(Some(&3), c.last());
+            assert_eq!("Frozen([4, 2, 3])", format!("{:?}", c));
+        }
+        assert_eq!([1, 2], *a);
+        assert_eq!([1, 2], *a.frozen());
+    }
+
+    #[test]
+    fn test_as_slice() {
+        let mut a = vec![3, 1, 1, 2];
+        let mut other = vec![5];
+        assert_eq!([3, 1, 1, 2, 5], *a.appended(&mut other).as_slice());
+        assert_eq!([4, 1, 1, 2], *a.assigned(0, 4).as_slice());
+        assert_eq!([3, 1, 2], *a.deduped().as_slice());
+        assert_eq!([3, 2], *a.drained(1..3).as_slice());
+        assert_eq!([3, 1, 1, 2, 5], *a.extended(vec![5]).as_slice());
+        assert_eq!([3, 1, 1, 2, 5], *a.extended_from_slice(&[5]).as_slice());
+        assert_eq!([0, 0, 0, 0], *a.filled(0).as_slice());
+        assert_eq!([3, 5, 1, 1, 2], *a.inserted(1, 5).as_slice());
+        assert_eq!([6, 2, 2, 4], *a.mapped(|x| x * 2).as_slice());
+        assert_eq!([3, 1, 1, 2], *a.nooped().as_slice());
+        assert_eq!([3, 1, 1], *a.popped().as_slice());
+        assert_eq!([3, 1, 1, 2, 5], *a.pushed(5).as_slice());
+        assert_eq!([1, 1, 2], *a.removed(0).as_slice());
+        assert_eq!([3, 1], *a.resized(2, 0).as_slice());
+        assert_eq!([1, 1], *a.retained(|&x| x == 1).as_slice());
+        assert_eq!([2, 1, 1, 3], *a.reversed().as_slice());
+        assert_eq!([1, 1, 2, 3], *a.rotated_left(1).as_slice());
+        assert_eq!([1, 1, 2, 3], *a.sorted().as_slice());
+        assert_eq!([1, 1, 2, 3], *a.sorted_tracked().as_slice());
+        assert_eq!([3], *a.split_off_at(1).as_slice());
+        assert_eq!([2, 1, 1], *a.swap_removed(0).as_slice());
+        assert_eq!([1, 3, 1, 2], *a.swapped(0, 1).as_slice());
+        assert_eq!([3, 1], *a.truncated(2).as_slice());
+    }
+
+    #[test]
+    fn test_spliced() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            let mut b = a.spliced(1..3, vec![9, 9, 9]);
+            assert_eq!([1, 9, 9, 9, 4], *b);
+            assert_eq!([1, 4], *b.spliced(1..4, vec![]));
+            assert_eq!([1, 9, 9, 9, 4], *b);
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_spliced_empty_range() {
+        let mut a = vec![1, 2];
+        {
+            assert_eq!([1, 5, 2], *a.spliced(1..1, Some(5)));
+        }
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_surviving_indices() {
+        let mut a = vec![1, 2, 3, 4];
+        assert_eq!(vec![1, 3], a.retained(|x| x % 2 == 0).surviving_indices());
+        assert_eq!(vec![0, 1, 2, 3], a.retained(|_| true).surviving_indices());
+        assert!(a.retained(|_| false).surviving_indices().is_empty());
+        assert_eq!(vec![0, 3], a.drained(1..3).surviving_indices());
+        assert_eq!(vec![2, 3], a.drained(..2).surviving_indices());
+        assert_eq!([1, 2, 3, 4], *a);
+    }
+
+    #[test]
+    fn test_removed_count() {
+        let mut a = vec![1, 2, 3, 4, 5, 6];
+        assert_eq!(3, a.retained(|&x| x % 2 == 0).removed_count());
+        assert_eq!(
+            3,
+            a.retained_mut(|x| {
+                *x += 1;
+                *x % 2 == 0
+            })
+            .removed_count()
+        );
+        assert_eq!(3, a.drained(1..4).removed_count());
+        assert_eq!(0, a.deduped().removed_count());
+        let mut b = vec![1, 1, 2, 2, 2, 3];
+        assert_eq!(3, b.deduped().removed_count());
+        assert_eq!([1, 2, 3, 4, 5, 6], *a);
+        assert_eq!([1, 1, 2, 2, 2, 3], *b);
+    }
+
+    #[test]
+    fn test_retained_mut() {
+        let mut a = vec![2, 3, 4, 5, 6];
+        {
+            let mut b = a.retained_mut(|x| {
+                let keep = *x % 2 == 0;
+                *x /= 2;
+                keep
+            });
+            assert_eq!([1, 2, 3], *b);
+            assert_eq!([1, 2, 3, 7], *b.pushed(7));
+        }
+        assert_eq!([2, 3, 4, 5, 6], *a);
+    }
+
+    #[test]
+    fn test_index_mut() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.truncated(2);
+            b[1] = 20;
+            let mut c = b.inserted(0, 0);
+            c[0] = 100;
+            c[2] = 200;
+            let mut d = c.extended(vec![4, 5]);
+            d[1] = 10;
+            d[4] = 50;
+            assert_eq!([100, 10, 200, 4, 50], *d);
+            d[1..3].copy_from_slice(&[7, 8]);
+            assert_eq!([100, 7, 8, 4, 50], *d);
+        }
+        // The outer ops still restore by length and position, keeping edits to existing elements
+        assert_eq!([7, 8, 3], *a);
+    }
+
+    #[test]
+    fn test_index_mut_reordered() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.inserted(0, 9);
+            b.swap(0, 3);
+            assert_eq!([3, 1, 2, 9], *b);
+            b[0] = 4;
+        }
+        // Reverting removes whatever is at the inserted index, so the original 3 is lost instead
+        assert_eq!([1, 2, 9], *a);
+    }
+
+    #[test]
+    fn test_defer_revert() {
+        let mut a = vec![1, 2, 3];
+        let len = defer_revert(
+            &mut a,
+            |v| assert_eq!(Some(4), v.pop()),
+            |v| {
+                v.push(4);
+                assert_eq!([1, 2, 3, 4], **v);
+                v.len()
+            },
+        );
+        assert_eq!(4, len);
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_defer_revert_after_panic() {
+        let mut a = vec![1, 2, 3];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            defer_revert(
+                &mut a,
+                |v| {
+                    v.pop();
+                },
+                |v| {
+                    v.push(4);
+                    panic!("panicking while deferred");
+                },
+            )
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_assign_replace_with() {
+        let mut a = vec!["a".to_string(), "b".to_string()];
+        {
+            let b = Assign::replace_with(&mut a, 1, |s| s.to_uppercase() + "!");
+            assert_eq!(["a", "B!"], *b);
+            assert_eq!("b", b.previous());
+        }
+        assert_eq!(["a", "b"], *a);
+    }
+
+    #[test]
+    #[should_panic(expected = "assigned index (is 2) should be < len (is 1)")]
+    fn test_assign_replace_with_out_of_bounds() {
+        let mut a = vec![1];
+        let _b = Assign::replace_with(&mut a, 2, |x| x + 1);
+    }
+
+    #[test]
+    fn test_reverted_in_reverse_order() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.pushed(4);
+            {
+                let mut c = b.assigned(3, 5);
+                {
+                    let mut d = c.popped();
+                    {
+                        let e = d.inserted(0, 6);
+                        assert_eq!([6, 1, 2, 3], *e);
+                        assert_eq!(4, e.depth());
+                    }
+                    assert_eq!([1, 2, 3], *d);
+                    assert_eq!(3, d.depth());
+                }
+                assert_eq!([1, 2, 3, 5], *c);
+            }
+            assert_eq!([1, 2, 3, 4], *b);
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_run_scoped() {
+        let mut a = vec![1, 2];
+        let len = run_scoped(&mut a, |b| {
+            b.push(3);
+            b.assign(0, 5);
+            assert_eq!([5, 2, 3], **b);
+            b.len()
+        });
+        assert_eq!(3, len);
+        assert_eq!([1, 2], *a);
+    }
+
+    #[test]
+    fn test_run_scoped_committed() {
+        fn push_if(a: &mut Vec<i32>, keep: bool) -> usize {
+            run_scoped_committed(a, |b| {
+                b.push(3);
+                if keep {
+                    Outcome::Commit(b.len())
+                } else {
+                    Outcome::Revert(b.len())
+                }
+            })
+        }
+
+        let mut a = vec![1, 2];
+        assert_eq!(3, push_if(&mut a, false));
+        assert_eq!([1, 2], *a);
+        assert_eq!(3, push_if(&mut a, true));
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_dyn_scoped_vec_commit() {
+        let mut a = vec![1, 2];
+        let mut b = DynScopedVec::new(&mut a);
+        b.pop();
+        b.push(3);
+        b.commit();
+        assert_eq!([1, 3], *a);
+    }
+
+    #[test]
+    fn test_assigned_many() {
+        let mut a = vec![1, 2, 3];
+        {
+            let b = a.assigned_many(vec![(0, 9), (2, 7)]);
+            assert_eq!([9, 2, 7], *b);
+            assert_eq!(vec![(0, &1), (2, &3)], b.diff());
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_assigned_many_repeated_index() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.assigned_many(vec![(1, 5), (1, 6), (0, 4), (1, 7)]);
+            assert_eq!([4, 7, 3], *b);
+            let c = b.assigned_many(vec![(1, 8)]);
+            assert_eq!([4, 8, 3], *c);
+        }
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_assigned_many_panics_before_assigning() {
+        let mut a = vec![1, 2, 3];
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _ = a.assigned_many(vec![(0, 9), (3, 7)]);
+        }));
+        assert!(result.is_err());
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_assigned_or_extended() {
+        let mut a = vec![1, 2];
+        {
+            let b = a.assigned_or_extended(1, 5);
+            assert_eq!([1, 5], *b);
+        }
+        assert_eq!([1, 2], *a);
+        {
+            let mut b = a.assigned_or_extended(2, 3);
+            assert_eq!([1, 2, 3], *b);
+            let c = b.assigned_or_extended(5, 6);
+            assert_eq!([1, 2, 3, 0, 0, 6], *c);
+        }
+        assert_eq!([1, 2], *a);
+        assert_eq!(
+            [1, 2, 0, 4],
+            *with_assigned_or_extended(&mut a, 3, 4, |b| b.snapshot())
+        );
+        a.assigned_or_extended(2, 3).commit();
+        assert_eq!([1, 2, 3], *a);
+    }
+
+    #[test]
+    fn test_op_kind() {
+        let mut a = vec![3, 1, 2];
+        assert_eq!(OpKind::Assign, a.assigned(0, 1).op_kind());
+        assert_eq!(OpKind::Noop, a.nooped().op_kind());
+        assert_eq!(OpKind::Pop, a.popped().op_kind());
+        assert_eq!(OpKind::Push, a.pushed(4).op_kind());
+        assert_eq!(OpKind::Remove, a.removed(0).op_kind());
+        assert_eq!(
+            OpKind::Reverting,
+            a.with_undo(|v| v.reverse(), |v| v.reverse()).op_kind()
+        );
+        assert_eq!(OpKind::Sort, a.sorted().op_kind());
+        assert_eq!(OpKind::Truncate, a.truncated(1).op_kind());
+        // Only the outermost operation is reported
+        assert_eq!(OpKind::Pop, a.pushed(4).popped().op_kind());
+    }
+
+    #[cfg(all(feature = "log", feature = "std"))]
+    #[test]
+    fn test_log() {
+        use std::cell::RefCell;
+
+        std::thread_local! {
+            static MESSAGES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+        }
+
+        /// Keeps each thread's messages separate, since tests run in parallel
+        struct CapturingLogger;
+
+        impl log::Log for CapturingLogger {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &log::Record) {
+                MESSAGES.with(|messages| messages.borrow_mut().push(record.args().to_string()));
+            }
+
+            fn flush(&self) {}
+        }
+
+        static LOGGER: CapturingLogger = CapturingLogger;
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            log::set_logger(&LOGGER).unwrap();
+            log::set_max_level(log::LevelFilter::Trace);
+        });
+
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.pushed(4);
+            let _c = b.assigned(0, 5);
+        }
+        a.popped().commit();
+        {
+            let _b = a.with_undo(|v| v.reverse(), |v| v.reverse());
+        }
+        assert_eq!(
+            vec![
+                "applied Push",
+                "applied Assign",
+                "reverted Assign",
+                "reverted Push",
+                "applied Pop",
+                "applied Reverting",
+                "reverted Reverting",
+            ],
+            MESSAGES.with(|messages| messages.borrow().clone())
+        );
+    }
+
+    #[test]
+    fn test_retained_in() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        {
+            let b = a.retained_in(1..4, |&x| x % 2 == 0);
+            assert_eq!([1, 2, 4, 5], *b);
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+        {
+            let mut b = a.retained_in(0..5, |&x| x > 2);
+            assert_eq!([3, 4, 5], *b);
+            let c = b.retained_in(1..3, |_| false);
+            assert_eq!([3], *c);
+        }
+        assert_eq!([1, 2, 3, 4, 5], *a);
+        assert_eq!([1, 2, 3, 4, 5], *a.retained_in(2..2, |_| false));
+    }
+
+    #[test]
+    #[should_panic(expected = "retained range (is 2..6) should be within len (is 5)")]
+    fn test_retained_in_out_of_bounds() {
+        let mut a = vec![1, 2, 3, 4, 5];
+        let _b = a.retained_in(2..6, |_| true);
+    }
+
+    #[test]
+    fn test_replaced() {
+        let mut a = vec!["a".to_string(), "b".to_string()];
+        {
+            let b = a.replaced(0, "c".to_string());
+            assert_eq!(["c", "b"], *b);
+            assert_eq!("a", b.taken());
+        }
+        assert_eq!(["a", "b"], *a);
+        assert_eq!("b", a.replaced(1, "e".to_string()).into_taken());
+        assert_eq!(["a", "e"], *a);
+    }
+
+    #[test]
+    #[should_panic(expected = "replaced index (is 1) should be < len (is 1)")]
+    fn test_replaced_out_of_bounds() {
+        let mut a = vec![1];
+        let _b = a.replaced(1, 2);
+    }
+
+    #[test]
+    fn test_with_undo() {
+        let mut a = vec![1, 2, 3, 4];
+        {
+            let mut b = a.with_undo(|v| v.rotate_left(1), |v| v.rotate_right(1));
+            assert_eq!([2, 3, 4, 1], *b);
+            let c = b.pushed(5);
+            assert_eq!([2, 3, 4, 1, 5], *c);
+            assert_eq!(2, c.depth());
+        }
+        assert_eq!([1, 2, 3, 4], *a);
+        assert_eq!(
+            "Reverting([1, 2])",
+            format!("{:?}", a.with_undo(|v| v.truncate(2), |v| v.extend([3, 4])))
+        );
+        a.with_undo(|v| v.reverse(), |v| v.reverse()).commit();
+        assert_eq!([4, 3, 2, 1], *a);
+    }
+
+    #[test]
+    fn test_pushed_all() {
+        let mut a = vec![1, 2, 3];
+        {
+            let mut b = a.pushed_all(vec![4, 5, 6]);
+            assert_eq!([1, 2, 3, 4, 5, 6], *b);
+            {
+                let c = b.pushed(7);
+                assert_eq!([1, 2, 3, 4, 5, 6, 7], *c);
+            }
+            assert_eq!([1, 2, 3, 4, 5, 6], *b);
+        }
+        assert_eq!([1, 2, 3], *a);
+        assert_eq!([1, 2, 3], *a.pushed_all(vec![]));
+    }
+
+    #[test]
+    fn test_mapped_indexed() {
+        let mut a = vec![10, 20, 30];
+        {
+            let mut b = a.mapped_indexed(|idx, _| idx);
+            assert_eq!([0, 1, 2], *b);
+            let c = b.mapped_indexed(|idx, &x| x * 10 + idx);
+            assert_eq!([0, 11, 22], *c);
+        }
+        assert_eq!([10, 20, 30], *a);
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut a = vec![1, 2, 3];
+        assert!(a.diff().is_empty());
+        let mut b = a.assigned(0, 4);
+        let mut c = b.pushed(5);
+        let d = c.assigned(2, 6);
+        assert_eq!([4, 2, 6, 5], *d);
+        assert_eq!(vec![(2, &3), (0, &1)], d.diff());
+        drop(d);
+        assert_eq!(vec![(0, &1)], c.diff());
+    }
+
+    /// A tiny stack with a fixed capacity, to check that `pushed` and `popped` don't depend on
+    /// `Vec`
+    #[cfg(test)]
+    #[derive(Debug, PartialEq)]
+    struct FixedStack {
+        items: [i32; 4],
+        len: usize,
+    }
+
+    #[cfg(test)]
+    impl Deref for FixedStack {
+        type Target = [i32];
+
+        fn deref(&self) -> &[i32] {
+            &self.items[..self.len]
+        }
+    }
+
+    #[cfg(test)]
+    impl PushPopStorage for FixedStack {
+        type Element = i32;
+
+        fn push(&mut self, value: i32) {
+            assert!(self.len < self.items.len(), "FixedStack is full");
+            self.items[self.len] = value;
+            self.len += 1;
+        }
+
+        fn pop(&mut self) -> Option<i32> {
+            self.len = self.len.checked_sub(1)?;
+            Some(self.items[self.len])
+        }
+
+        fn get_mut(&mut self, idx: usize) -> Option<&mut i32> {
+            self.items[..self.len].get_mut(idx)
+        }
+
+        fn len(&self) -> usize {
+            self.len
+        }
+    }
+
+    #[test]
+    fn test_custom_storage() {
+        let mut a = FixedStack {
+            items: [1, 2, 0, 0],
+            len: 2,
+        };
+        {
+            let mut b = a.pushed(3);
+            assert_eq!([1, 2, 3], *b);
+            assert_eq!(3, b.len());
+            let mut c = b.popped();
+            let mut d = c.popped();
+            assert_eq!([1], *d);
+            let mut e = d.assigned(0, 5);
+            assert_eq!([5], *e.nooped());
+            assert_eq!(4, e.depth());
+        }
+        assert_eq!([1, 2], *a);
+        assert!(a.try_assigned(2, 5).is_err());
+    }
+
+    #[cfg(all(debug_assertions, feature = "std"))]
+    #[test]
+    #[should_panic(expected = "committed an operation at depth 2")]
+    fn test_commit_beneath_live_op_panics() {
+        let mut a = vec![1];
+        let mut b = a.pushed(2);
+        b.pushed(3).commit();
+    }
+
+    #[cfg(all(debug_assertions, feature = "std"))]
+    #[test]
+    #[should_panic(expected = "reverted an operation at depth 1 with 2 live operations")]
+    fn test_forget_beneath_live_op_panics() {
+        let mut a = vec![1];
+        let mut b = a.pushed(2);
+        core::mem::forget(b.pushed(3));
+        drop(b);
+    }
+
+    #[cfg(all(debug_assertions, feature = "std"))]
+    #[test]
+    fn test_commit_beneath_live_op_reverts_cleanly() {
+        let mut a = vec![1];
+        {
+            let mut b = a.pushed(2);
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                b.pushed(3).commit();
+            }));
+            assert!(result.is_err());
+            assert_eq!([1, 2], *b);
+        }
+        assert_eq!([1], *a);
+        a.pushed(4).commit();
+        assert_eq!([1, 4], *a);
+    }
+
+    // `tests/ui/must_use_pushed.rs` checks that this warns
+    #[test]
+    #[allow(unused_must_use)]
+    fn test_must_use() {
+        let mut a = vec![1];
+        a.pushed(2); // This pushes a value that is then immediately popped, which is useless
+        assert_eq!([1], *a);
+    }
+
+    // I don't think this can work b/c the type is different each iteration of the loop. There's a
+    // similar issue with recursion. With a complicated enough system of generics this could be used but
+    // overall it's probably not worth the trouble. `DynScopedVec` works in loops instead.
+    //
+    // #[test]
+    // fn test_loop() {
+    //     let mut a: Box<dyn VecScoped<i32>> = Box::new(vec![]);
+    //     for i in 0..3 {
+    //         a = a.pushed(i);
+    //     }
+    // }
+}
+
+pub mod owned {
+    pub trait VecScopedPrivate {
+        type Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element>;
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element>;
+
+        /// The number of scoped operations between this and the underlying `Vec`
+        fn vec_depth(&self) -> usize;
+
+        /// Unwrap all the way down to the underlying `Vec` without reverting anything
+        fn into_vec(self) -> Vec<Self::Element>
+        where
+            Self: Sized;
+
+        /// Revert every operation, unwrapping all the way down to the underlying `Vec`
+        fn into_reverted_vec(self) -> Vec<Self::Element>
+        where
+            Self: Sized;
+    }
+
+    #[cfg(test)]
+    use alloc::format;
+    #[cfg(test)]
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::fmt;
+    use core::ops::Deref;
+
+    /// This trait represent a `Vec` or a temporary modification of a `Vec`
+    pub trait VecScoped<T>: VecScopedPrivate<Element = T> {
+        /// Temporarily assign an element at `idx` of the `Vec`.
+        /// Panics if `idx` is out of bounds.
+        fn assigned(self, idx: usize, value: T) -> Assign<Self>
+        where
+            Self: Sized,
+        {
+            Assign::new(self, value, idx)
+        }
+
+        /// Temporarily remove all of the elements of the `Vec`
+        fn cleared(self) -> Clear<Self>
+        where
+            Self: Sized,
+        {
+            Clear::new(self)
+        }
+
+        /// Returns `true` if the current state of the `Vec` contains an element equal to `x`
+        fn contains(&self, x: &T) -> bool
+        where
+            T: PartialEq,
+        {
+            self.vec_ref().as_slice().contains(x)
+        }
+
+        /// Returns the number of scoped operations that are currently applied, which is how many
+        /// reverts are still pending. This is 0 for a plain `Vec`.
+        fn depth(&self) -> usize {
+            self.vec_depth()
+        }
+
+        /// Temporarily append all of the items of `iter` onto the end of the `Vec`
+        fn extended<I: IntoIterator<Item = T>>(self, iter: I) -> Extend<Self>
+        where
+            Self: Sized,
+        {
+            Extend::new(self, iter)
+        }
+
+        /// Returns the first element of the current state of the `Vec`, if there is one
+        fn first(&self) -> Option<&T> {
+            self.vec_ref().as_slice().first()
+        }
+
+        /// Returns the element at `idx` in the current state of the `Vec`, if it's in bounds
+        fn get(&self, idx: usize) -> Option<&T> {
+            self.vec_ref().as_slice().get(idx)
+        }
+
+        /// Temporarily insert an element at position `idx` of the `Vec`, shifting all elements
+        /// after it to the right.
+        /// Panics if `idx > len`.
+        fn inserted(self, idx: usize, value: T) -> Insert<Self>
+        where
+            Self: Sized,
+        {
+            Insert::new(self, idx, value)
+        }
+
+        /// Returns `true` if the current state of the `Vec` has no elements
+        fn is_empty(&self) -> bool {
+            self.vec_ref().is_empty()
+        }
+
+        /// Iterates over the current state of the `Vec` from back to front, so the most recently
+        /// pushed element comes first
+        fn iter_rev(&self) -> core::iter::Rev<core::slice::Iter<'_, T>> {
+            self.vec_ref().iter().rev()
+        }
+
+        /// Returns the last element of the current state of the `Vec`, if there is one
+        fn last(&self) -> Option<&T> {
+            self.vec_ref().as_slice().last()
+        }
+
+        /// Make every operation in this chain permanent at once and return the underlying `Vec`.
+        /// This is like calling `commit` at each level. In the `borrowed` module, each level of the
+        /// chain is owned by a different scope, so there's no equivalent there.
+        fn leak(self) -> Vec<T>
+        where
+            Self: Sized,
+        {
+            self.into_vec()
+        }
+
+        /// Returns the number of elements in the current state of the `Vec`
+        fn len(&self) -> usize {
+            self.vec_ref().len()
+        }
+
+        /// This can be used to turn a `Vec` into a `VecScoped`
+        fn nooped(self) -> Noop<Self>
+        where
+            Self: Sized,
+        {
+            Noop::new(self)
+        }
+
+        /// Temporarily pop the last element from the end of the `Vec`
+        fn popped(self) -> Pop<Self>
+        where
+            Self: Sized,
+        {
+            Pop::new(self)
+        }
+
+        /// Temporarily push an element onto the end of the `Vec`
+        fn pushed(self, value: T) -> Push<Self>
+        where
+            Self: Sized,
+        {
+            Push::new(self, value)
+        }
+
+        /// Temporarily remove the element at position `idx` of the `Vec`, shifting all elements
+        /// after it to the left.
+        /// Panics if `idx` is out of bounds.
+        fn removed(self, idx: usize) -> Remove<Self>
+        where
+            Self: Sized,
+        {
+            Remove::new(self, idx)
+        }
+
+        /// Returns a copy of the current state of the `Vec`, which isn't reverted along with it
+        fn snapshot(&self) -> Vec<T>
+        where
+            T: Clone,
+        {
+            self.vec_ref().clone()
+        }
+
+        /// Returns the current state of the `Vec` split into two slices at `mid`.
+        /// Panics if `mid > len`.
+        fn split_view(&self, mid: usize) -> (&[T], &[T]) {
+            self.vec_ref().split_at(mid)
+        }
+
+        /// Temporarily shorten the `Vec` to `len` elements. If `len` is greater than or equal to
+        /// the current length, this has no effect.
+        fn truncated(self, len: usize) -> Truncate<Self>
+        where
+            Self: Sized,
+        {
+            Truncate::new(self, len)
+        }
+    }
+
+    impl<T> VecScopedPrivate for Vec<T> {
+        type Element = T;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self
+        }
+
+        fn vec_depth(&self) -> usize {
+            0
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self
+        }
+    }
+
+    impl<T> VecScoped<T> for Vec<T> {}
+
+    /// See `crate::owned::VecScoped::assigned`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Assign<V: VecScopedPrivate> {
+        inner: V,
+        idx: usize,
+        previous: V::Element,
+    }
+
+    impl<V: VecScopedPrivate> Assign<V> {
+        pub fn new(mut inner: V, mut value: V::Element, idx: usize) -> Self {
+            let vec = inner.vec_mut();
+            if let Some(old) = vec.get_mut(idx) {
+                core::mem::swap(old, &mut value);
+            } else {
+                panic!(
+                    "assigned index (is {}) should be < len (is {})",
+                    idx,
+                    vec.len()
+                )
+            }
+            Self {
+                inner,
+                idx,
+                previous: value,
+            }
+        }
+
+        pub fn into_inner(mut self) -> V {
+            let idx = self.idx;
+            let vec = self.inner.vec_mut();
+            if let Some(old) = vec.get_mut(idx) {
+                core::mem::swap(old, &mut self.previous);
+            } else {
+                panic!(
+                    "restoring assigned index (is {}) should be < len (is {}), this should never happen",
+                    idx,
+                    vec.len()
+                )
+            }
+            self.inner
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Assign<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug
+        for Assign<V>
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Assign")
+                .field("vec", &&**self)
+                .field("idx", &self.idx)
+                .field("previous", &self.previous)
+                .finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Assign<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.inner.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.inner.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.inner.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Assign<V> {}
+
+    /// See `crate::owned::VecScoped::cleared`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Clear<V: VecScopedPrivate> {
+        inner: V,
+        cleared: Vec<V::Element>,
+    }
+
+    impl<V: VecScopedPrivate> Clear<V> {
+        pub fn new(mut inner: V) -> Self {
+            let vec = inner.vec_mut();
+            let cleared = core::mem::replace(vec, Vec::with_capacity(vec.capacity()));
+            Self { inner, cleared }
+        }
+
+        pub fn into_inner(mut self) -> V {
+            let vec = self.inner.vec_mut();
+            debug_assert!(vec.is_empty(), "Someone has illicitly pushed an element!");
+            vec.append(&mut self.cleared);
+            self.inner
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Clear<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug
+        for Clear<V>
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Clear").field(&&**self).finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Clear<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.inner.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.inner.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.inner.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Clear<V> {}
+
+    /// See `crate::owned::VecScoped::extended`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Extend<V: VecScopedPrivate> {
+        inner: V,
+        count: usize,
+    }
+
+    impl<V: VecScopedPrivate> Extend<V> {
+        pub fn new<I: IntoIterator<Item = V::Element>>(mut inner: V, iter: I) -> Self {
+            let vec = inner.vec_mut();
+            let len_before = vec.len();
+            vec.extend(iter);
+            let count = vec.len() - len_before;
+            Self { inner, count }
+        }
+
+        pub fn into_inner(mut self) -> V {
+            let vec = self.inner.vec_mut();
+            debug_assert!(
+                vec.len() >= self.count,
+                "Someone has illicitly popped an element!"
+            );
+            vec.truncate(vec.len().saturating_sub(self.count));
+            self.inner
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Extend<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug
+        for Extend<V>
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Extend").field(&&**self).finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Extend<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.inner.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.inner.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.inner.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Extend<V> {}
+
+    /// See `crate::owned::VecScoped::inserted`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Insert<V: VecScopedPrivate> {
+        inner: V,
+        idx: usize,
+    }
+
+    impl<V: VecScopedPrivate> Insert<V> {
+        pub fn new(mut inner: V, idx: usize, value: V::Element) -> Self {
+            let vec = inner.vec_mut();
+            if idx > vec.len() {
+                panic!(
+                    "insertion index (is {}) should be <= len (is {})",
+                    idx,
+                    vec.len()
+                )
+            }
+            vec.insert(idx, value);
+            Self { inner, idx }
+        }
+
+        pub fn into_inner(mut self) -> V {
+            let idx = self.idx;
+            self.vec_mut().remove(idx);
+            self.inner
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Insert<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug
+        for Insert<V>
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Insert").field(&&**self).finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Insert<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.inner.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.inner.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.inner.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Insert<V> {}
+
+    /// See `crate::owned::VecScoped::nooped`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Noop<V: VecScopedPrivate>(V);
+
+    impl<V: VecScopedPrivate> Noop<V> {
+        pub fn new(inner: V) -> Self {
+            Self(inner)
+        }
+
+        pub fn into_inner(self) -> V {
+            self.0
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Noop<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug for Noop<V> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Noop").field(&&**self).finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Noop<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.0.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.0.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.0.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.0.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Noop<V> {}
+
+    /// See `crate::owned::VecScoped::pop`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Pop<V: VecScopedPrivate> {
+        inner: V,
+        popped: Option<V::Element>,
+    }
+
+    impl<V: VecScopedPrivate> Pop<V> {
+        pub fn new(mut inner: V) -> Self {
+            let popped = inner.vec_mut().pop();
+            Self { inner, popped }
+        }
+
+        pub fn into_inner(mut self) -> V {
+            if let Some(popped) = self.popped.take() {
+                self.vec_mut().push(popped)
+            }
+            self.inner
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Pop<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug for Pop<V> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Pop")
+                .field("vec", &&**self)
+                .field("popped", &self.popped)
+                .finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Pop<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.inner.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.inner.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.inner.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Pop<V> {}
+
+    /// See `crate::owned::VecScoped::pushed`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Push<V: VecScopedPrivate>(V);
+
+    impl<V: VecScopedPrivate> Push<V> {
+        pub fn new(mut inner: V, value: V::Element) -> Self {
+            inner.vec_mut().push(value);
+            Self(inner)
+        }
+
+        pub fn into_inner(mut self) -> V {
+            let _did_pop = self.vec_mut().pop().is_some();
+            debug_assert!(_did_pop, "Someone has illicitly popped an element!");
+            self.0
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Push<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.0
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug for Push<V> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Push").field(&&**self).finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Push<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.0.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.0.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.0.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.0.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Push<V> {}
+
+    /// See `crate::owned::VecScoped::removed`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Remove<V: VecScopedPrivate> {
+        inner: V,
+        idx: usize,
+        removed: V::Element,
+    }
+
+    impl<V: VecScopedPrivate> Remove<V> {
+        pub fn new(mut inner: V, idx: usize) -> Self {
+            let vec = inner.vec_mut();
+            if idx >= vec.len() {
+                panic!(
+                    "removal index (is {}) should be < len (is {})",
+                    idx,
+                    vec.len()
+                )
+            }
+            let removed = vec.remove(idx);
+            Self {
+                inner,
+                idx,
+                removed,
+            }
+        }
+
+        pub fn into_inner(mut self) -> V {
+            self.inner.vec_mut().insert(self.idx, self.removed);
+            self.inner
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Remove<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug
+        for Remove<V>
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Remove").field(&&**self).finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Remove<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.inner.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.inner.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.inner.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Remove<V> {}
+
+    /// See `crate::owned::VecScoped::truncated`
+    #[must_use = "this scoped operation owns the `Vec`, which is dropped if this isn't used"]
+    pub struct Truncate<V: VecScopedPrivate> {
+        inner: V,
+        tail: Vec<V::Element>,
+    }
+
+    impl<V: VecScopedPrivate> Truncate<V> {
+        pub fn new(mut inner: V, len: usize) -> Self {
+            let vec = inner.vec_mut();
+            let tail = if len < vec.len() {
+                vec.split_off(len)
+            } else {
+                Vec::new()
+            };
+            Self { inner, tail }
+        }
+
+        pub fn into_inner(mut self) -> V {
+            self.inner.vec_mut().append(&mut self.tail);
+            self.inner
+        }
+    }
+
+    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Truncate<V> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.inner
+        }
+    }
+
+    impl<T: fmt::Debug, V: Deref<Target = [T]> + VecScopedPrivate<Element = T>> fmt::Debug
+        for Truncate<V>
+    {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Truncate").field(&&**self).finish()
+        }
+    }
+
+    impl<V: VecScopedPrivate> VecScopedPrivate for Truncate<V> {
+        type Element = V::Element;
+
+        fn vec_ref(&self) -> &Vec<Self::Element> {
+            self.inner.vec_ref()
+        }
+
+        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+            self.inner.vec_mut()
+        }
+
+        fn vec_depth(&self) -> usize {
+            self.inner.vec_depth() + 1
+        }
+
+        fn into_vec(self) -> Vec<Self::Element> {
+            self.inner.into_vec()
+        }
+
+        fn into_reverted_vec(self) -> Vec<Self::Element> {
+            self.into_inner().into_reverted_vec()
+        }
+    }
+
+    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Truncate<V> {}
+
+    impl_slice_traits!(
+        VecScopedPrivate: Assign, Clear, Extend, Insert, Noop, Pop, Push, Remove, Truncate,
+    );
+
+    /// Implements `From` for `Vec`, so that `let v: Vec<_> = chain.into()` reverts the whole chain
+    macro_rules! impl_from_for_vec {
+        ($($ty:ident),* $(,)?) => {$(
+            impl<T, V: VecScopedPrivate<Element = T>> From<$ty<V>> for Vec<T> {
+                fn from(scoped: $ty<V>) -> Self {
+                    scoped.into_reverted_vec()
+                }
+            }
+        )*};
+    }
+
+    impl_from_for_vec!(Assign, Clear, Extend, Insert, Noop, Pop, Push, Remove, Truncate);
+
+    #[test]
+    fn test_assigned() {
+        let a = vec![5, 6];
+        let b = a.assigned(0, 9);
+        assert_eq!([9, 6], *b);
+        assert_eq!([5, 6], *b.into_inner());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assigned_panics_with_out_of_bounds_index() {
+        let _ = vec![1].assigned(2, 5);
+    }
+
+    #[test]
+    fn test_noop() {
+        let a = vec![1, 2];
+        let b = a.nooped();
+        assert_eq!([1, 2], *b);
+        assert_eq!([1, 2], *b.into_inner());
+    }
+
+    #[test]
+    fn test_noop_chain() {
+        let b = vec![1, 2].nooped().pushed(3).assigned(0, 4);
+        assert_eq!([4, 2, 3], *b);
+        assert_eq!([1, 2], *b.into_inner().into_inner().into_inner());
+    }
+
+    #[test]
+    fn test_pop() {
+        let a = vec![1];
+        let b = a.popped();
+        assert_eq!([0i32; 0], *b);
+        assert_eq!([1], *b.into_inner());
+    }
+
+    #[test]
+    fn test_push() {
+        let a = vec![1];
+        let b = a.pushed(2);
+        assert_eq!([1, 2], *b);
+        assert_eq!([1], *b.into_inner());
+    }
+
+    #[test]
+    fn test_pop_push() {
+        let a = vec![1, 2];
+        let b = a.popped().pushed(9);
+        assert_eq!([1, 9], *b);
+        let c = b.into_inner();
+        assert_eq!([1], *c);
+        assert_eq!([1, 2], *c.into_inner());
+    }
+
+    #[test]
+    fn test_inserted() {
+        let b = vec![1, 3].inserted(1, 2);
+        assert_eq!([1, 2, 3], *b);
+        assert_eq!([1, 3], *b.into_inner());
+        assert_eq!([1, 3, 4], *vec![1, 3].inserted(2, 4));
+    }
+
+    #[test]
+    #[should_panic(expected = "insertion index (is 3) should be <= len (is 2)")]
+    fn test_inserted_panics_with_out_of_bounds_index() {
+        let _ = vec![1, 2].inserted(3, 5);
+    }
+
+    #[test]
+    fn test_removed() {
+        let b = vec![1, 2, 3].removed(1);
+        assert_eq!([1, 3], *b);
+        assert_eq!([1, 2, 3], *b.into_inner());
+    }
+
+    #[test]
+    #[should_panic(expected = "removal index (is 2) should be < len (is 2)")]
+    fn test_removed_panics_with_out_of_bounds_index() {
+        let _ = vec![1, 2].removed(2);
+    }
+
+    #[test]
+    fn test_truncated() {
+        let b = vec![1, 2, 3].truncated(1);
+        assert_eq!([1], *b);
+        assert_eq!([1, 2, 3], *b.into_inner());
+        assert_eq!([1, 2], *vec![1, 2].truncated(5));
+    }
+
+    #[test]
+    fn test_insert_remove_truncate_chain() {
+        let b = vec![1, 2, 3]
+            .truncated(2)
+            .inserted(0, 0)
+            .removed(2)
+            .pushed(9);
+        assert_eq!([0, 1, 9], *b);
+        assert_eq!(4, b.depth());
+        let c = b.into_inner();
+        assert_eq!([0, 1], *c);
+        let d = c.into_inner();
+        assert_eq!([0, 1, 2], *d);
+        let e = d.into_inner();
+        assert_eq!([1, 2], *e);
+        assert_eq!([1, 2, 3], *e.into_inner());
+    }
+
+    #[test]
+    fn test_cleared() {
+        let b = vec![1, 2, 3].cleared().pushed(9);
+        assert_eq!([9], *b);
+        let c = b.into_inner();
+        assert!(c.is_empty());
+        assert_eq!([1, 2, 3], *c.into_inner());
+    }
+
+    #[test]
+    fn test_extended() {
+        let b = vec![1, 2].extended(vec![3, 4]).popped();
+        assert_eq!([1, 2, 3], *b);
+        let c = b.into_inner();
+        assert_eq!([1, 2, 3, 4], *c);
+        assert_eq!([1, 2], *c.into_inner());
+        assert_eq!(vec![1], Vec::from(vec![1].cleared().extended(0..3)));
+    }
+
+    #[test]
+    fn test_debug() {
+        let assign = vec![1, 2].assigned(0, 3);
+        assert_eq!(
+            "Assign { vec: [3, 2], idx: 0, previous: 1 }",
+            format!("{:?}", assign)
+        );
+        let noop = assign.into_inner().nooped();
+        assert_eq!("Noop([1, 2])", format!("{:?}", noop));
+        let pop = noop.into_inner().popped();
+        assert_eq!("Pop { vec: [1], popped: Some(2) }", format!("{:?}", pop));
+        let push = pop.into_inner().pushed(4);
+        assert_eq!("Push([1, 2, 4])", format!("{:?}", push));
+    }
+
+    #[test]
+    fn test_as_ref() {
+        fn sum(v: impl AsRef<[i32]>) -> i32 {
+            v.as_ref().iter().sum()
+        }
+
+        let push = vec![1, 2, 3].pushed(4);
+        assert_eq!(10, sum(&push));
+        let pop = push.into_inner().popped();
+        assert_eq!(3, sum(&pop));
+        let assign = pop.into_inner().assigned(0, 3);
+        assert_eq!(8, sum(&assign));
+        assert_eq!(6, sum(assign.into_inner().nooped()));
+    }
+
+    #[test]
+    fn test_display() {
+        let push = vec![1, 2, 3].pushed(4);
+        assert_eq!("[1, 2, 3, 4]", format!("{}", push));
+        assert_eq!("[1, 2, 3]", format!("{}", push.into_inner().nooped()));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        let mut pushed = DefaultHasher::new();
+        vec![1, 2, 3].pushed(4).hash(&mut pushed);
+        let mut slice = DefaultHasher::new();
+        [1, 2, 3, 4][..].hash(&mut slice);
+        assert_eq!(slice.finish(), pushed.finish());
+    }
+
+    #[test]
+    fn test_eq_slice() {
+        let assign = vec![1, 2].assigned(0, 3);
+        assert_eq!(assign, [3, 2]);
+        let noop = assign.into_inner().nooped();
+        assert_eq!(noop, vec![1, 2]);
+        let pop = noop.into_inner().popped();
+        assert_eq!(pop, [1][..]);
+        let push = pop.into_inner().pushed(4);
+        assert_eq!(push, &[1, 2, 4][..]);
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let push = vec![1, 2].pushed(3);
+        assert_eq!(vec![&1, &2, &3], (&push).into_iter().collect::<Vec<_>>());
+        let pop = push.into_inner().popped();
+        assert_eq!(vec![&1], (&pop).into_iter().collect::<Vec<_>>());
+        let assign = pop.into_inner().assigned(0, 4);
+        assert_eq!(vec![&4, &2], (&assign).into_iter().collect::<Vec<_>>());
+        let noop = assign.into_inner().nooped();
+        assert_eq!(vec![&1, &2], (&noop).into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_index() {
+        let push = vec![1, 2].pushed(3);
+        assert_eq!(3, push[2]);
+        let assign = push.assigned(0, 4);
+        assert_eq!([4, 2], assign[..2]);
+        let pop = assign.into_inner().into_inner().popped();
+        assert_eq!(1, pop[0]);
+        let noop = pop.into_inner().nooped();
+        assert_eq!([1, 2], noop[0..2]);
+    }
+
+    #[test]
+    fn test_len() {
+        let push = vec![1].pushed(2);
+        assert_eq!(2, push.len());
+        let pop = push.into_inner().popped().popped();
+        assert_eq!(0, pop.len());
+        assert!(pop.is_empty());
+    }
+
+    #[test]
+    fn test_read_only_helpers() {
+        let assign = vec![1, 2].assigned(0, 3);
+        assert_eq!(Some(&3), assign.first());
+        assert_eq!(Some(&2), assign.last());
+        assert!(!assign.contains(&1));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let push = vec![1, 2].pushed(3);
+        let snapshot = push.snapshot();
+        assert_eq!([1, 2], *push.into_inner());
+        assert_eq!(vec![1, 2, 3], snapshot);
+    }
+
+    #[test]
+    fn test_split_view() {
+        let push = vec![1, 2].pushed(3);
+        assert_eq!((&[1][..], &[2, 3][..]), push.split_view(1));
+    }
+
+    #[test]
+    fn test_iter_rev() {
+        let push = vec![1, 2].pushed(9);
+        assert_eq!(Some(&9), push.iter_rev().next());
+        assert_eq!(Some(&2), push.into_inner().iter_rev().next());
+    }
+
+    #[test]
+    fn test_depth() {
+        let a = vec![1, 2].pushed(1).pushed(2).assigned(0, 5);
+        assert_eq!(3, a.depth());
+        assert_eq!(2, a.into_inner().depth());
+    }
+
+    #[test]
+    fn test_leak() {
+        let a = vec![1, 2].pushed(3).pushed(4).assigned(0, 5);
+        assert_eq!(vec![5, 2, 3, 4], a.leak());
+        assert_eq!(vec![1], vec![1, 2].popped().nooped().leak());
+    }
+
+    #[test]
+    fn test_into_vec() {
+        let a: Vec<_> = vec![1, 2].popped().into();
+        assert_eq!(vec![1, 2], a);
+        let chain = vec![1, 2, 3]
+            .truncated(2)
+            .inserted(0, 0)
+            .removed(1)
+            .pushed(4)
+            .assigned(0, 5)
+            .nooped();
+        assert_eq!([5, 2, 4], *chain);
+        assert_eq!(vec![1, 2, 3], Vec::from(chain));
+    }
+
+    #[test]
+    fn test_get() {
+        let a = vec![1, 2].pushed(3);
+        assert_eq!(Some(&3), a.get(2));
+        assert_eq!(None, a.into_inner().get(2));
+    }
+
+    #[test]
+    fn test_as_slice() {
+        let push = vec![1, 2].pushed(3);
+        assert_eq!([1, 2, 3], *push.as_slice());
+        let assign = push.into_inner().assigned(0, 4);
+        assert_eq!([4, 2], *assign.as_slice());
+        let pop = assign.into_inner().popped();
+        assert_eq!([1], *pop.as_slice());
+        assert_eq!([1, 2], *pop.into_inner().nooped().as_slice());
+    }
+}
+
+pub mod rc {
+    //! A reference-counted variant, where each scoped operation holds a handle to a shared
+    //! `Rc<RefCell<Vec<T>>>` instead of a mutable reference. Scoped values don't borrow from each
+    //! other, so they can be stored and moved around freely. In exchange, nothing enforces that they
+    //! are dropped in reverse order; dropping them out of order may not restore the original state.
+
+    #[cfg(test)]
+    use alloc::format;
+    use alloc::rc::Rc;
+    #[cfg(test)]
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::cell::{Ref, RefCell};
+    use core::fmt;
+
+    /// Everything that is `VecScoped` holds a handle to the shared `Vec`. Only the trait
+    /// implementations should mutate the `Vec` through this handle.
+    pub trait VecScopedPrivate {
+        type Element;
+
+        fn vec_rc(&self) -> &Rc<RefCell<Vec<Self::Element>>>;
+    }
+
+    /// This trait represent a shared `Vec` or a temporary modification of a shared `Vec`
+    pub trait VecScoped<T>: VecScopedPrivate<Element = T> {
+        /// Temporarily assign an element at `idx` of the `Vec`.
+        /// Panics if `idx` is out of bounds.
+        fn assigned(&self, idx: usize, value: T) -> Assign<T> {
+            Assign::new(self.vec_rc().clone(), value, idx)
+        }
+
+        /// Immutably borrow the current state of the `Vec`. Don't hold onto this while a scoped
+        /// operation is applied or reverted, or the `RefCell` will panic.
+        fn borrow_vec(&self) -> Ref<'_, Vec<T>> {
+            self.vec_rc().borrow()
+        }
+
+        /// Temporarily pop the last element from the end of the `Vec`
+        fn popped(&self) -> Pop<T> {
+            Pop::new(self.vec_rc().clone())
         }
 
         /// Temporarily push an element onto the end of the `Vec`
-        fn pushed(&mut self, value: T) -> Push<Self>
-        where
-            Self: Sized,
-        {
-            Push::new(self, value)
+        fn pushed(&self, value: T) -> Push<T> {
+            Push::new(self.vec_rc().clone(), value)
         }
     }
 
-    impl<T> VecScopedPrivate for Vec<T> {
+    impl<T> VecScopedPrivate for Rc<RefCell<Vec<T>>> {
         type Element = T;
 
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
+        fn vec_rc(&self) -> &Rc<RefCell<Vec<Self::Element>>> {
             self
         }
     }
 
-    impl<T> VecScoped<T> for Vec<T> {}
+    impl<T> VecScoped<T> for Rc<RefCell<Vec<T>>> {}
 
-    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Assign<'a, V> {
-        type Target = [T];
+    /// See `crate::rc::VecScoped::assigned`
+    ///
+    /// Cloning this gives another handle to the same operation, which is reverted once every clone
+    /// has been dropped.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Assign<T> {
+        revert: Rc<AssignRevert<T>>,
+    }
 
-        fn deref(&self) -> &Self::Target {
-            self.inner
-        }
+    /// The state shared by all clones of an `Assign`, which reverts it when dropped
+    struct AssignRevert<T> {
+        vec: Rc<RefCell<Vec<T>>>,
+        idx: usize,
+        previous: Option<T>,
     }
 
-    impl<'a, V: VecScopedPrivate> Drop for Assign<'a, V> {
-        fn drop(&mut self) {
-            let idx = self.idx;
-            let inner = self.inner.vec_mut();
-            if let Some(old) = inner.get_mut(idx) {
-                std::mem::swap(old, &mut self.previous);
-            } else {
-                panic!(
-                    "dropping assigned index (is {}) should be < len (is {}), this should never happen",
-                    idx,
-                    inner.len()
-                )
+    impl<T> Assign<T> {
+        pub fn new(vec: Rc<RefCell<Vec<T>>>, mut value: T, idx: usize) -> Self {
+            {
+                let mut inner = vec.borrow_mut();
+                let len = inner.len();
+                if let Some(old) = inner.get_mut(idx) {
+                    core::mem::swap(old, &mut value);
+                } else {
+                    panic!("assigned index (is {}) should be < len (is {})", idx, len)
+                }
             }
+            let revert = Rc::new(AssignRevert {
+                vec,
+                idx,
+                previous: Some(value),
+            });
+            Self { revert }
         }
     }
 
-    impl<'a, V: VecScopedPrivate> VecScopedPrivate for Assign<'a, V> {
-        type Element = V::Element;
-
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
-            self.inner.vec_mut()
+    impl<T> Clone for Assign<T> {
+        fn clone(&self) -> Self {
+            Self {
+                revert: self.revert.clone(),
+            }
         }
     }
 
-    impl<'a, T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Assign<'a, V> {}
-
-    /// See `crate::borrowed::VecScoped::noop`
-    #[must_use]
-    pub struct Noop<'a, V: VecScopedPrivate>(&'a mut V);
-
-    impl<'a, V: VecScopedPrivate> Noop<'a, V> {
-        pub fn new(vec_scoped: &'a mut V) -> Self {
-            Self(vec_scoped)
+    impl<T> Drop for AssignRevert<T> {
+        fn drop(&mut self) {
+            if let Some(mut previous) = self.previous.take() {
+                let idx = self.idx;
+                let mut inner = self.vec.borrow_mut();
+                let len = inner.len();
+                if let Some(old) = inner.get_mut(idx) {
+                    core::mem::swap(old, &mut previous);
+                } else {
+                    panic!(
+                        "dropping assigned index (is {}) should be < len (is {}), this should never happen",
+                        idx,
+                        len
+                    )
+                }
+            }
         }
     }
 
-    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Noop<'a, V> {
-        type Target = [T];
-
-        fn deref(&self) -> &Self::Target {
-            self.0
+    impl<T: fmt::Debug> fmt::Debug for Assign<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Assign")
+                .field("vec", &&**self.borrow_vec())
+                .field("idx", &self.revert.idx)
+                .field("previous", self.revert.previous.as_ref().unwrap())
+                .finish()
         }
     }
 
-    impl<'a, V: VecScopedPrivate> VecScopedPrivate for Noop<'a, V> {
-        type Element = V::Element;
+    impl<T> VecScopedPrivate for Assign<T> {
+        type Element = T;
 
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
-            self.0.vec_mut()
+        fn vec_rc(&self) -> &Rc<RefCell<Vec<Self::Element>>> {
+            &self.revert.vec
         }
     }
 
-    impl<'a, T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Noop<'a, V> {}
+    impl<T> VecScoped<T> for Assign<T> {}
 
-    /// See `crate::borrowed::VecScoped::pop`
-    #[must_use]
-    pub struct Pop<'a, V: VecScopedPrivate> {
-        inner: &'a mut V,
-        popped: Option<V::Element>,
+    /// See `crate::rc::VecScoped::popped`
+    ///
+    /// Cloning this gives another handle to the same pop; the element is only pushed back once
+    /// the last clone is dropped.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Pop<T> {
+        revert: Rc<PopRevert<T>>,
     }
 
-    impl<'a, V: VecScopedPrivate> Pop<'a, V> {
-        pub fn new(inner: &'a mut V) -> Self {
-            let popped = inner.vec_mut().pop();
-            Self { inner, popped }
-        }
+    /// The state shared by all clones of a `Pop`, which reverts it when dropped
+    struct PopRevert<T> {
+        vec: Rc<RefCell<Vec<T>>>,
+        popped: Option<T>,
     }
 
-    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Pop<'a, V> {
-        type Target = [T];
+    impl<T> Pop<T> {
+        pub fn new(vec: Rc<RefCell<Vec<T>>>) -> Self {
+            let popped = vec.borrow_mut().pop();
+            let revert = Rc::new(PopRevert { vec, popped });
+            Self { revert }
+        }
+    }
 
-        fn deref(&self) -> &Self::Target {
-            self.inner
+    impl<T> Clone for Pop<T> {
+        fn clone(&self) -> Self {
+            Self {
+                revert: self.revert.clone(),
+            }
         }
     }
 
-    impl<'a, V: VecScopedPrivate> Drop for Pop<'a, V> {
+    impl<T> Drop for PopRevert<T> {
         fn drop(&mut self) {
             if let Some(popped) = self.popped.take() {
-                self.vec_mut().push(popped)
+                self.vec.borrow_mut().push(popped)
             }
         }
     }
 
-    impl<'a, V: VecScopedPrivate> VecScopedPrivate for Pop<'a, V> {
-        type Element = V::Element;
-
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
-            self.inner.vec_mut()
+    impl<T: fmt::Debug> fmt::Debug for Pop<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_struct("Pop")
+                .field("vec", &&**self.borrow_vec())
+                .field("popped", &self.revert.popped)
+                .finish()
         }
     }
 
-    impl<'a, T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Pop<'a, V> {}
-
-    /// See `crate::borrowed::VecScoped::push`
-    #[must_use]
-    pub struct Push<'a, V: VecScopedPrivate>(&'a mut V);
+    impl<T> VecScopedPrivate for Pop<T> {
+        type Element = T;
 
-    impl<'a, V: VecScopedPrivate> Push<'a, V> {
-        pub fn new(vec_scoped: &'a mut V, value: V::Element) -> Self {
-            vec_scoped.vec_mut().push(value);
-            Self(vec_scoped)
+        fn vec_rc(&self) -> &Rc<RefCell<Vec<Self::Element>>> {
+            &self.revert.vec
         }
     }
 
-    impl<'a, T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Push<'a, V> {
-        type Target = [T];
+    impl<T> VecScoped<T> for Pop<T> {}
 
-        fn deref(&self) -> &Self::Target {
-            self.0
-        }
+    /// See `crate::rc::VecScoped::pushed`
+    ///
+    /// Cloning this gives another handle to the same push; the element is only popped once the
+    /// last clone is dropped.
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct Push<T> {
+        revert: Rc<PushRevert<T>>,
     }
 
-    impl<'a, V: VecScopedPrivate> Drop for Push<'a, V> {
-        fn drop(&mut self) {
-            let _did_pop = self.0.vec_mut().pop().is_some();
-            debug_assert!(_did_pop, "Someone has illicitly popped an element!");
-        }
+    /// The state shared by all clones of a `Push`, which reverts it when dropped
+    struct PushRevert<T> {
+        vec: Rc<RefCell<Vec<T>>>,
     }
 
-    impl<'a, V: VecScopedPrivate> VecScopedPrivate for Push<'a, V> {
-        type Element = V::Element;
-
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
-            self.0.vec_mut()
+    impl<T> Push<T> {
+        pub fn new(vec: Rc<RefCell<Vec<T>>>, value: T) -> Self {
+            vec.borrow_mut().push(value);
+            let revert = Rc::new(PushRevert { vec });
+            Self { revert }
         }
     }
 
-    impl<'a, T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Push<'a, V> {}
-
-    pub struct Assign<'a, V: VecScopedPrivate> {
-        inner: &'a mut V,
-        idx: usize,
-        previous: V::Element,
-    }
-
-    impl<'a, V: VecScopedPrivate> Assign<'a, V> {
-        pub fn new(vec_scoped: &'a mut V, mut value: V::Element, idx: usize) -> Self {
-            let inner = vec_scoped.vec_mut();
-            if let Some(old) = inner.get_mut(idx) {
-                std::mem::swap(old, &mut value);
-            } else {
-                panic!(
-                    "assigned index (is {}) should be < len (is {})",
-                    idx,
-                    inner.len()
-                )
-            }
+    impl<T> Clone for Push<T> {
+        fn clone(&self) -> Self {
             Self {
-                inner: vec_scoped,
-                idx,
-                previous: value,
+                revert: self.revert.clone(),
             }
         }
     }
 
-    #[test]
-    fn test_scoped_vec() {
-        let mut a = vec![1];
-        {
-            let mut b = a.pushed(2);
-            {
-                assert_eq!([1, 2, 3], *b.pushed(3));
-            }
-            assert_eq!([1, 2, -3], *b.pushed(-3));
+    impl<T> Drop for PushRevert<T> {
+        fn drop(&mut self) {
+            let _did_pop = self.vec.borrow_mut().pop().is_some();
+            debug_assert!(_did_pop, "Someone has illicitly popped an element!");
         }
-        assert_eq!([1, -2], *a.pushed(-2));
-        assert_eq!([1], *a);
     }
 
-    #[test]
-    fn test_noop() {
-        let mut a = vec![1, 2, 3];
-        {
-            assert_eq!([1, 2, 3], *a.nooped());
+    impl<T: fmt::Debug> fmt::Debug for Push<T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("Push").field(&&**self.borrow_vec()).finish()
         }
-        assert_eq!([1, 2, 3], *a);
     }
 
-    #[test]
-    fn test_pop_empty() {
-        let mut a = Vec::<i32>::new();
-        {
-            assert_eq!([0i32; 0], *a.popped());
+    impl<T> VecScopedPrivate for Push<T> {
+        type Element = T;
+
+        fn vec_rc(&self) -> &Rc<RefCell<Vec<Self::Element>>> {
+            &self.revert.vec
         }
-        assert_eq!([0i32; 0], *a);
     }
 
+    impl<T> VecScoped<T> for Push<T> {}
+
     #[test]
-    fn test_pop() {
-        let mut a = vec![1];
-        {
-            assert_eq!([0i32; 0], *a.popped());
-        }
-        assert_eq!([1], *a);
+    fn test_clone() {
+        let a = Rc::new(RefCell::new(vec![1, 2]));
+        let push = a.pushed(3);
+        let push_clone = push.clone();
+        drop(push);
+        assert_eq!([1, 2, 3], **a.borrow_vec());
+        let pop = push_clone.popped();
+        let pop_clone = pop.clone();
+        let assign = pop_clone.assigned(0, 4).clone();
+        assert_eq!([4, 2], **a.borrow_vec());
+        drop(assign);
+        drop(pop_clone);
+        assert_eq!([1, 2], **a.borrow_vec());
+        drop(pop);
+        assert_eq!([1, 2, 3], **a.borrow_vec());
+        drop(push_clone);
+        assert_eq!([1, 2], **a.borrow_vec());
     }
 
     #[test]
-    fn test_pop_push() {
-        let mut a = vec![1];
+    fn test_shared_handles() {
+        let a = Rc::new(RefCell::new(vec![1, 2, 3]));
+        let b = a.clone();
         {
-            assert_eq!([-1], *a.popped().pushed(-1));
+            let c = a.pushed(4);
+            assert_eq!([1, 2, 3, 4], **b.borrow_vec());
+            {
+                let d = b.assigned(0, 5);
+                assert_eq!([5, 2, 3, 4], **a.borrow_vec());
+                assert_eq!([5, 2, 3, 4], **c.borrow_vec());
+                assert_eq!([5, 2, 3, 4], **d.borrow_vec());
+            }
+            assert_eq!([1, 2, 3, 4], **a.borrow_vec());
+            assert_eq!([1, 2, 3, 4], **b.borrow_vec());
         }
-        assert_eq!([1], *a);
+        assert_eq!([1, 2, 3], **a.borrow_vec());
+        assert_eq!([1, 2, 3], **b.borrow_vec());
     }
 
     #[test]
-    fn test_assigned() {
-        let mut a = vec![0, 1, 2, 3];
-        {
-            assert_eq!([0, 1, 5, 3], *a.assigned(2, 5))
-        }
-        assert_eq!([0, 1, 2, 3], *a);
+    fn test_reverse_order() {
+        let a = Rc::new(RefCell::new(vec![1]));
+        let p = a.pushed(2);
+        let q = p.assigned(0, 3);
+        let r = q.popped();
+        assert_eq!([3], **a.borrow_vec());
+        drop(r);
+        assert_eq!([3, 2], **a.borrow_vec());
+        drop(q);
+        assert_eq!([1, 2], **a.borrow_vec());
+        drop(p);
+        assert_eq!([1], **a.borrow_vec());
     }
 
     #[test]
-    #[should_panic]
-    fn test_assigned_panics_with_out_of_bounds_index() {
-        vec![1].assigned(2, 5);
+    fn test_loop() {
+        let a = Rc::new(RefCell::new(vec![]));
+        let mut scopes = Vec::new();
+        for i in 0..3 {
+            scopes.push(a.pushed(i));
+        }
+        assert_eq!([0, 1, 2], **a.borrow_vec());
+        while let Some(scope) = scopes.pop() {
+            drop(scope);
+        }
+        assert_eq!([0i32; 0], **a.borrow_vec());
     }
 
-    // TODO automatically verify that this warns
     #[test]
-    fn test_must_use() {
-        let mut a = vec![1];
-        a.pushed(2); // This pushes a value that is then immediately popped, which is useless
-        assert_eq!([1], *a);
+    fn test_debug() {
+        let a = Rc::new(RefCell::new(vec![1, 2]));
+        let push = a.pushed(3);
+        assert_eq!("Push([1, 2, 3])", format!("{:?}", push));
+        let assign = push.assigned(0, 4);
+        assert_eq!(
+            "Assign { vec: [4, 2, 3], idx: 0, previous: 1 }",
+            format!("{:?}", assign)
+        );
+        let pop = assign.popped();
+        assert_eq!("Pop { vec: [4, 2], popped: Some(3) }", format!("{:?}", pop));
     }
-
-    // I don't think this can work b/c the type is different each iteration of the loop. There's a
-    // similar issue with recursion. With a complicated enough system of generics this could be used but
-    // overall it's probably not worth the trouble.
-    //
-    // #[test]
-    // fn test_loop() {
-    //     let mut a: Box<dyn VecScoped<i32>> = Box::new(vec![]);
-    //     for i in 0..3 {
-    //         a = a.pushed(i);
-    //     }
-    // }
 }
 
-pub mod owned {
-    pub trait VecScopedPrivate {
-        type Element;
+pub mod cow {
+    //! A copy-on-write variant, where each scope only has a shared reference to the state before
+    //! it. Nothing is cloned until a scope actually changes something, so read-only scopes are
+    //! free. A changed scope owns a clone of the state, so reverting it is just dropping the clone.
+    //! Each changing operation clones the state it starts from, so this suits chains that mostly
+    //! read, rather than long chains of changes.
 
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element>;
-    }
+    use alloc::borrow::Cow;
+    #[cfg(test)]
+    use alloc::format;
+    #[cfg(test)]
+    use alloc::vec;
+    #[cfg(test)]
+    use alloc::vec::Vec;
+    use core::fmt;
+    use core::ops::Deref;
 
-    use std::ops::Deref;
+    /// A scope over a slice, which clones it the first time that it's changed
+    #[must_use = "this scoped operation reverts immediately if not bound"]
+    pub struct CowScoped<'a, T: Clone> {
+        vec: Cow<'a, [T]>,
+    }
 
-    /// This trait represent a `Vec` or a temporary modification of a `Vec`
-    pub trait VecScoped<T>: VecScopedPrivate<Element = T> {
-        /// Temporarily pop the last element from the end of the `Vec`
-        fn popped(self) -> Pop<Self>
-        where
-            Self: Sized,
-        {
-            Pop::new(self)
+    impl<'a, T: Clone> CowScoped<'a, T> {
+        /// Start a scope over `vec`, which is never modified
+        pub fn new(vec: &'a [T]) -> Self {
+            Self {
+                vec: Cow::Borrowed(vec),
+            }
         }
-    }
 
-    impl<T> VecScopedPrivate for Vec<T> {
-        type Element = T;
+        /// A nested scope that starts from the current state, without cloning anything
+        pub fn nooped(&self) -> CowScoped<'_, T> {
+            CowScoped::new(self)
+        }
 
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
-            self
+        /// A nested scope with an element pushed onto the end. This clones the current state.
+        pub fn pushed(&self, value: T) -> CowScoped<'_, T> {
+            let mut scoped = self.nooped();
+            scoped.vec.to_mut().push(value);
+            scoped
         }
-    }
 
-    impl<T> VecScoped<T> for Vec<T> {}
+        /// A nested scope with the element at `idx` assigned. This clones the current state.
+        /// Panics if `idx` is out of bounds.
+        pub fn assigned(&self, idx: usize, value: T) -> CowScoped<'_, T> {
+            let len = self.len();
+            if idx >= len {
+                panic!("assigned index (is {}) should be < len (is {})", idx, len)
+            }
+            let mut scoped = self.nooped();
+            scoped.vec.to_mut()[idx] = value;
+            scoped
+        }
 
-    /// See `crate::owned::VecScoped::pop`
-    #[must_use]
-    pub struct Pop<V: VecScopedPrivate> {
-        inner: V,
-        popped: Option<V::Element>,
+        /// Returns `true` if this scope has cloned the state that it started from
+        pub fn is_cloned(&self) -> bool {
+            matches!(self.vec, Cow::Owned(_))
+        }
     }
 
-    impl<V: VecScopedPrivate> Pop<V> {
-        pub fn new(mut inner: V) -> Self {
-            let popped = inner.vec_mut().pop();
-            Self { inner, popped }
+    impl<'a, T: Clone> Deref for CowScoped<'a, T> {
+        type Target = [T];
+
+        fn deref(&self) -> &Self::Target {
+            &self.vec
         }
+    }
 
-        pub fn into_inner(mut self) -> V {
-            if let Some(popped) = self.popped.take() {
-                self.vec_mut().push(popped)
-            }
-            self.inner
+    impl<'a, T: Clone + fmt::Debug> fmt::Debug for CowScoped<'a, T> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.debug_tuple("CowScoped").field(&&**self).finish()
         }
     }
 
-    impl<T, V: Deref<Target = [T]> + VecScopedPrivate> Deref for Pop<V> {
-        type Target = [T];
+    #[cfg(test)]
+    use alloc::rc::Rc;
+    #[cfg(test)]
+    use core::cell::Cell;
 
-        fn deref(&self) -> &Self::Target {
-            &self.inner
+    /// Counts how many times it has been cloned, across all of its clones
+    #[cfg(test)]
+    #[derive(Debug, PartialEq)]
+    struct Counted {
+        value: i32,
+        clones: Rc<Cell<usize>>,
+    }
+
+    #[cfg(test)]
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            self.clones.set(self.clones.get() + 1);
+            Self {
+                value: self.value,
+                clones: self.clones.clone(),
+            }
         }
     }
 
-    impl<V: VecScopedPrivate> VecScopedPrivate for Pop<V> {
-        type Element = V::Element;
+    #[test]
+    fn test_read_only_scope_never_clones() {
+        let clones = Rc::new(Cell::new(0));
+        let a = vec![Counted {
+            value: 1,
+            clones: clones.clone(),
+        }];
+        let b = CowScoped::new(&a);
+        let c = b.nooped();
+        assert_eq!(1, c[0].value);
+        assert!(!c.is_cloned());
+        assert_eq!(0, clones.get());
+    }
 
-        fn vec_mut(&mut self) -> &mut Vec<Self::Element> {
-            self.inner.vec_mut()
+    #[test]
+    fn test_changing_scope_clones() {
+        let clones = Rc::new(Cell::new(0));
+        let counted = |value| Counted {
+            value,
+            clones: clones.clone(),
+        };
+        let a = vec![counted(1), counted(2)];
+        let b = CowScoped::new(&a);
+        {
+            let c = b.pushed(counted(3));
+            assert!(c.is_cloned());
+            assert_eq!(2, clones.get());
+            let d = c.assigned(0, counted(4));
+            assert_eq!(vec![4, 2, 3], d.iter().map(|x| x.value).collect::<Vec<_>>());
+            // The scope that `d` started from is unchanged
+            assert_eq!(1, c[0].value);
+            assert_eq!(5, clones.get());
         }
+        assert!(!b.is_cloned());
+        assert_eq!(vec![1, 2], a.iter().map(|x| x.value).collect::<Vec<_>>());
     }
 
-    impl<T, V: VecScopedPrivate<Element = T>> VecScoped<T> for Pop<V> {}
+    #[test]
+    fn test_debug() {
+        let a = vec![1, 2];
+        let b = CowScoped::new(&a);
+        assert_eq!("CowScoped([1, 2, 3])", format!("{:?}", b.pushed(3)));
+    }
 
     #[test]
-    fn test_pop() {
-        let a = vec![1];
-        let b = a.popped();
-        assert_eq!([0i32; 0], *b);
-        assert_eq!([1], *b.into_inner());
+    #[should_panic(expected = "assigned index (is 2) should be < len (is 2)")]
+    fn test_assigned_out_of_bounds() {
+        let a = vec![1, 2];
+        let _b = CowScoped::new(&a).assigned(2, 3);
     }
 }
@@ -0,0 +1,7 @@
+use scoped_ops::borrowed::VecScoped;
+
+fn main() {
+    let mut a = vec![1];
+    let mut b = a.pushed(2).frozen();
+    let _ = b.pushed(3);
+}
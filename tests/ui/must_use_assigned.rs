@@ -0,0 +1,7 @@
+#![deny(unused_must_use)]
+use scoped_ops::borrowed::VecScoped;
+
+fn main() {
+    let mut a = vec![1];
+    a.assigned(0, 5);
+}